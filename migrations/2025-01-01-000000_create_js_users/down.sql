@@ -0,0 +1 @@
+DROP TABLE js_users;
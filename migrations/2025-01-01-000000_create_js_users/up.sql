@@ -0,0 +1,5 @@
+-- scripts/db_test.js 使用的示例表；PostgreSQL 与 SQLite 通用，不依赖自增主键
+CREATE TABLE js_users (
+    name TEXT NOT NULL,
+    email TEXT NOT NULL
+);
@@ -2,30 +2,26 @@
 
 async function runTests() {
     try {
-        // 1. Setup Table
-        await db.execute("CREATE TABLE IF NOT EXISTS js_users (id SERIAL PRIMARY KEY, name TEXT NOT NULL, email TEXT NOT NULL)");
-        
-        // 2. Insert
+        // js_users 由 migrations/2025-01-01-000000_create_js_users 创建（ujs-web-svr migrate）
+
+        // 1. Insert
         const insertSql = "INSERT INTO js_users (name, email) VALUES ('js_user', 'js@example.com')";
         const affectedRows = await db.execute(insertSql);
         
-        // 3. Query
+        // 2. Query
         const querySql = "SELECT name, email as res2 FROM js_users WHERE name = 'js_user'";
         const results = await db.query(querySql);
         
-        // 4. Update
+        // 3. Update
         const updateSql = "UPDATE js_users SET email = 'updated@example.com' WHERE name = 'js_user'";
         await db.execute(updateSql);
         
-        // 5. Query again to verify update
+        // 4. Query again to verify update
         const resultsAfterUpdate = await db.query(querySql);
         
-        // 6. Delete
+        // 5. Delete
         const deleteSql = "DELETE FROM js_users WHERE name = 'js_user'";
         await db.execute(deleteSql);
-        
-        // 7. Cleanup
-        await db.execute("DROP TABLE js_users");
 
         const response = {
             setup: "ok",
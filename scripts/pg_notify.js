@@ -0,0 +1,12 @@
+// scripts/pg_notify.js
+// PG_LISTEN_SCRIPT 示例：PG_LISTEN_CHANNELS=orders PG_LISTEN_SCRIPT=pg_notify.js
+// 每条 NOTIFY 以 POST /pg_notify/<channel> 的形式执行一次，请求体为 {channel, payload, process_id}
+
+const { channel, payload } = JSON.parse(globalThis.request.body());
+
+// user:<id> 是私有频道，只有认证身份为 <id> 的连接能订阅（见 WS_PRIVATE_CHANNEL_PREFIXES）
+if (channel === "orders" && payload?.status === "shipped") {
+    ws.publish(`user:${payload.user_id}`, { event: "order_shipped", order: payload.id });
+}
+
+Deno.core.ops.op_send_response({ status: 204, headers: {}, body: "" });
@@ -0,0 +1,24 @@
+// scripts/rpc/math/divide.ts
+// JSON-RPC 方法 math.divide：{"jsonrpc":"2.0","method":"math.divide","params":{"a":1,"b":0},"id":1}
+// 除数为 0 时抛出 RpcError，调用方收到 {"code":-32010,"message":"Division by zero","data":{"a":1}}
+
+export const schema = {
+    type: "object",
+    properties: {
+        a: { type: "number" },
+        b: { type: "number" },
+    },
+    required: ["a", "b"],
+} as const;
+
+const { a, b }: { a: number; b: number } = JSON.parse(globalThis.request.body());
+
+if (b === 0) {
+    throw new RpcError(-32010, "Division by zero", { a });
+}
+
+Deno.core.ops.op_send_response({
+    status: 200,
+    headers: { "Content-Type": "application/json" },
+    body: JSON.stringify(a / b)
+});
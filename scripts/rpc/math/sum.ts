@@ -0,0 +1,31 @@
+// scripts/rpc/math/sum.ts
+// JSON-RPC 方法 math.sum：{"jsonrpc":"2.0","method":"math.sum","params":{"numbers":[1,2,3]},"id":1}
+
+interface SumParams {
+    numbers: number[];
+}
+
+// 参数 schema：调用前由 JSON-RPC 层校验，不符合时返回 -32602，脚本不会执行
+export const schema = {
+    type: "object",
+    properties: {
+        numbers: { type: "array", items: { type: "number" } },
+    },
+    required: ["numbers"],
+} as const;
+
+const params: SumParams = JSON.parse(globalThis.request.body());
+
+if (!Array.isArray(params?.numbers) || params.numbers.some((n) => typeof n !== 'number')) {
+    Deno.core.ops.op_send_response({
+        status: 400,
+        headers: { "Content-Type": "application/json" },
+        body: JSON.stringify({ error: 'numbers must be an array of numbers' })
+    });
+} else {
+    Deno.core.ops.op_send_response({
+        status: 200,
+        headers: { "Content-Type": "application/json" },
+        body: JSON.stringify(params.numbers.reduce((sum, n) => sum + n, 0))
+    });
+}
@@ -0,0 +1,14 @@
+// scripts/session_counter.js
+// 基于签名 Cookie 的会话示例：GET 计数 +1，DELETE 销毁会话
+
+if (globalThis.request.method() === "DELETE") {
+    session.destroy();
+} else {
+    session.set("count", (session.get("count") ?? 0) + 1);
+}
+
+Deno.core.ops.op_send_response({
+    status: 200,
+    headers: { "Content-Type": "application/json" },
+    body: JSON.stringify({ count: session.get("count") ?? 0 })
+});
@@ -0,0 +1,25 @@
+// scripts/ws_chat.js
+// WebSocket 脚本示例：连接 ws://localhost:3001/ws/ws_chat.js
+// 每个连接运行在独立的 isolate 中，模块级变量只属于当前连接
+
+let received = 0;
+
+export function onOpen(socket) {
+    const agent = socket.headers["user-agent"] ?? "unknown";
+    console.info(`socket ${socket.id} opened by ${agent}`);
+    socket.send({ type: "welcome", id: socket.id });
+}
+
+export async function onMessage(socket, data) {
+    received += 1;
+    if (data === "bye") {
+        socket.close(1000, "bye");
+        return;
+    }
+    const rows = db.query("SELECT CURRENT_TIMESTAMP AS now");
+    socket.send({ type: "echo", data, count: received, now: rows[0]?.now ?? null });
+}
+
+export function onClose(socket, code, reason) {
+    console.info(`socket ${socket.id} closed (${code} ${reason}) after ${received} messages`);
+}
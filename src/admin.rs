@@ -0,0 +1,108 @@
+use crate::auth::AuthIdentity;
+use crate::logging::FilterHandle;
+use axum::{
+    Extension, Json, Router,
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use serde::Deserialize;
+use serde_json::json;
+use tracing_subscriber::EnvFilter;
+
+/// 是否挂载管理接口（`ADMIN_ENABLED`，默认关闭）
+pub fn enabled_from_env() -> bool {
+    dotenvy::dotenv().ok();
+    std::env::var("ADMIN_ENABLED").is_ok_and(|v| v == "true" || v == "1")
+}
+
+/// 管理接口路由
+///
+/// 与 `/js`、`/rpc` 一样经过认证中间件，要求已认证且带有 `admin` 角色；未启用认证时所有请求都被拒绝。
+pub fn router(filter: FilterHandle) -> Router {
+    Router::new()
+        .route("/admin/log-level", get(get_log_level).put(set_log_level))
+        .with_state(filter)
+}
+
+#[derive(Deserialize)]
+pub struct LogLevelRequest {
+    /// `RUST_LOG` 语法的过滤规则，如 `info,js=debug`
+    pub filter: String,
+}
+
+/// 没有身份或没有 `admin` 角色时拒绝
+fn forbidden(user: &Option<Extension<AuthIdentity>>) -> Option<Response> {
+    match user {
+        Some(Extension(identity)) if identity.has_role("admin") => None,
+        Some(_) => Some(
+            (
+                StatusCode::FORBIDDEN,
+                Json(json!({ "error": "admin role required" })),
+            )
+                .into_response(),
+        ),
+        None => Some(
+            (
+                StatusCode::UNAUTHORIZED,
+                Json(json!({ "error": "authentication required" })),
+            )
+                .into_response(),
+        ),
+    }
+}
+
+/// GET /admin/log-level
+async fn get_log_level(
+    State(filter): State<FilterHandle>,
+    user: Option<Extension<AuthIdentity>>,
+) -> Response {
+    if let Some(response) = forbidden(&user) {
+        return response;
+    }
+    match filter.with_current(|f| f.to_string()) {
+        Ok(current) => Json(json!({ "filter": current })).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))).into_response(),
+    }
+}
+
+/// PUT /admin/log-level
+async fn set_log_level(
+    State(filter): State<FilterHandle>,
+    user: Option<Extension<AuthIdentity>>,
+    Json(req): Json<LogLevelRequest>,
+) -> Response {
+    if let Some(response) = forbidden(&user) {
+        return response;
+    }
+    let new_filter = match EnvFilter::try_new(&req.filter) {
+        Ok(f) => f,
+        Err(e) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({ "error": e.to_string() }))).into_response();
+        }
+    };
+    match filter.reload(new_filter) {
+        Ok(()) => {
+            tracing::warn!("log filter changed to {}", req.filter);
+            Json(json!({ "filter": req.filter })).into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forbidden_requires_admin_identity() {
+        assert_eq!(forbidden(&None).unwrap().status(), StatusCode::UNAUTHORIZED);
+
+        let user = AuthIdentity::new("alice", "jwt", json!({ "roles": ["user"] }));
+        assert_eq!(forbidden(&Some(Extension(user))).unwrap().status(), StatusCode::FORBIDDEN);
+
+        let admin = AuthIdentity::new("root", "jwt", json!({ "roles": ["admin"] }));
+        assert!(forbidden(&Some(Extension(admin))).is_none());
+    }
+}
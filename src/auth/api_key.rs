@@ -0,0 +1,45 @@
+use crate::auth::AuthIdentity;
+use std::collections::HashMap;
+
+/// 静态 API Key 校验 - 单一职责：将 `X-Api-Key` 映射为身份
+#[derive(Debug, Clone, Default)]
+pub struct ApiKeyVerifier {
+    /// key -> subject
+    keys: HashMap<String, String>,
+}
+
+impl ApiKeyVerifier {
+    /// 从 `key:subject,key2:subject2` 格式解析
+    pub fn parse(spec: &str) -> Self {
+        let keys = spec
+            .split(',')
+            .filter_map(|entry| {
+                let (key, subject) = entry.trim().split_once(':')?;
+                Some((key.to_string(), subject.to_string()))
+            })
+            .collect();
+        Self { keys }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn verify(&self, key: &str) -> Option<AuthIdentity> {
+        self.keys
+            .get(key)
+            .map(|subject| AuthIdentity::new(subject, "api_key", serde_json::Value::Null))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_key_verify() {
+        let verifier = ApiKeyVerifier::parse("k1:alice, k2:bob");
+        assert_eq!(verifier.verify("k2").unwrap().subject, "bob");
+        assert!(verifier.verify("k3").is_none());
+    }
+}
@@ -0,0 +1,247 @@
+use crate::auth::AuthIdentity;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 允许的时间戳偏差（秒）
+const MAX_CLOCK_SKEW: u64 = 300;
+
+/// nonce 长度范围
+const NONCE_LEN: std::ops::RangeInclusive<usize> = 16..=128;
+
+/// HMAC 签名请求校验 - 单一职责：校验 `X-Key-Id` / `X-Timestamp` / `X-Nonce` / `X-Signature`
+///
+/// 签名内容为 `{METHOD}\n{path}\n{规范化查询串}\n{timestamp}\n{nonce}\n{hex(sha256(body))}`，
+/// 签名值为 HMAC-SHA256 的十六进制编码。规范化查询串见 [`HmacVerifier::canonical_query`]。
+/// 每个 nonce 在时间戳有效期内只能使用一次，重放的请求会被拒绝。
+#[derive(Debug, Clone, Default)]
+pub struct HmacVerifier {
+    /// key id -> secret
+    secrets: HashMap<String, Vec<u8>>,
+    seen: Arc<Mutex<SeenNonces>>,
+}
+
+/// 有效期内已使用的 nonce，按过期时间顺序记录以便清理
+#[derive(Debug, Default)]
+struct SeenNonces {
+    keys: HashSet<(String, String)>,
+    expiry: VecDeque<(u64, (String, String))>,
+}
+
+impl SeenNonces {
+    /// 记录 nonce，已使用过时返回 `false`
+    fn insert(&mut self, key_id: &str, nonce: &str, now: u64, expires_at: u64) -> bool {
+        while let Some((expires, _)) = self.expiry.front()
+            && *expires < now
+        {
+            let (_, key) = self.expiry.pop_front().unwrap();
+            self.keys.remove(&key);
+        }
+        let key = (key_id.to_string(), nonce.to_string());
+        if !self.keys.insert(key.clone()) {
+            return false;
+        }
+        // 过期时间相同或更晚的记录排在后面，保持队列有序
+        let position = self.expiry.partition_point(|(expires, _)| *expires <= expires_at);
+        self.expiry.insert(position, (expires_at, key));
+        true
+    }
+}
+
+/// 请求头中的签名凭证
+#[derive(Debug, Clone, Copy)]
+pub struct HmacCredentials<'a> {
+    pub key_id: &'a str,
+    pub timestamp: &'a str,
+    pub nonce: &'a str,
+    pub signature: &'a str,
+}
+
+impl HmacVerifier {
+    /// 从 `keyid:secret,keyid2:secret2` 格式解析
+    pub fn parse(spec: &str) -> Self {
+        let secrets = spec
+            .split(',')
+            .filter_map(|entry| {
+                let (id, secret) = entry.trim().split_once(':')?;
+                Some((id.to_string(), secret.as_bytes().to_vec()))
+            })
+            .collect();
+        Self {
+            secrets,
+            seen: Arc::default(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.secrets.is_empty()
+    }
+
+    /// 规范化查询串：参数解码后按名称、值排序，再按 `application/x-www-form-urlencoded` 编码
+    pub fn canonical_query(query: Option<&str>) -> String {
+        let mut pairs: Vec<(String, String)> = form_urlencoded::parse(query.unwrap_or("").as_bytes())
+            .map(|(k, v)| (k.into_owned(), v.into_owned()))
+            .collect();
+        pairs.sort();
+        form_urlencoded::Serializer::new(String::new()).extend_pairs(pairs).finish()
+    }
+
+    /// 构建待签名字符串
+    pub fn string_to_sign(
+        method: &str,
+        path: &str,
+        query: Option<&str>,
+        timestamp: &str,
+        nonce: &str,
+        body: &[u8],
+    ) -> String {
+        format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            method.to_uppercase(),
+            path,
+            Self::canonical_query(query),
+            timestamp,
+            nonce,
+            hex::encode(Sha256::digest(body))
+        )
+    }
+
+    pub fn verify(
+        &self,
+        credentials: &HmacCredentials,
+        method: &str,
+        path: &str,
+        query: Option<&str>,
+        body: &[u8],
+    ) -> Result<AuthIdentity, String> {
+        let HmacCredentials {
+            key_id,
+            timestamp,
+            nonce,
+            signature,
+        } = *credentials;
+        let secret = self
+            .secrets
+            .get(key_id)
+            .ok_or_else(|| "Unknown HMAC key id".to_string())?;
+
+        let ts: u64 = timestamp
+            .parse()
+            .map_err(|_| "Invalid X-Timestamp".to_string())?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        if now.abs_diff(ts) > MAX_CLOCK_SKEW {
+            return Err("Request timestamp out of range".to_string());
+        }
+        if !NONCE_LEN.contains(&nonce.len()) || !nonce.bytes().all(|b| b.is_ascii_graphic()) {
+            return Err("Invalid X-Nonce".to_string());
+        }
+
+        let expected = hex::decode(signature).map_err(|_| "Invalid X-Signature".to_string())?;
+        let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts any key length");
+        mac.update(Self::string_to_sign(method, path, query, timestamp, nonce, body).as_bytes());
+        mac.verify_slice(&expected)
+            .map_err(|_| "Signature mismatch".to_string())?;
+
+        // 签名通过后再记录 nonce，避免伪造请求占用其他客户端的 nonce；
+        // 时间戳超出有效期后请求本身会被拒绝，nonce 只需保留到那时
+        if !self.seen.lock().unwrap().insert(key_id, nonce, now, ts + MAX_CLOCK_SKEW) {
+            return Err("Replayed request".to_string());
+        }
+
+        Ok(AuthIdentity::new(key_id, "hmac", serde_json::Value::Null))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign(secret: &[u8], message: &str) -> String {
+        let mut mac = HmacSha256::new_from_slice(secret).unwrap();
+        mac.update(message.as_bytes());
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    fn creds<'a>(key_id: &'a str, timestamp: &'a str, nonce: &'a str, signature: &'a str) -> HmacCredentials<'a> {
+        HmacCredentials {
+            key_id,
+            timestamp,
+            nonce,
+            signature,
+        }
+    }
+
+    fn now() -> String {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            .to_string()
+    }
+
+    #[test]
+    fn test_hmac_verify() {
+        let verifier = HmacVerifier::parse("client1:s3cret");
+        let ts = now();
+        let nonce = "0123456789abcdef";
+        let message = HmacVerifier::string_to_sign("post", "/rpc", None, &ts, nonce, b"{}");
+        let signature = sign(b"s3cret", &message);
+
+        assert!(verifier
+            .verify(&creds("client1", &ts, nonce, &signature), "POST", "/rpc", None, b"{\"x\":1}")
+            .is_err());
+        assert!(verifier
+            .verify(&creds("client1", "1", nonce, &signature), "POST", "/rpc", None, b"{}")
+            .is_err());
+
+        let identity = verifier
+            .verify(&creds("client1", &ts, nonce, &signature), "POST", "/rpc", None, b"{}")
+            .unwrap();
+        assert_eq!(identity.subject, "client1");
+
+        // 同一请求不能重放
+        let err = verifier
+            .verify(&creds("client1", &ts, nonce, &signature), "POST", "/rpc", None, b"{}")
+            .unwrap_err();
+        assert_eq!(err, "Replayed request");
+    }
+
+    #[test]
+    fn test_hmac_signs_query() {
+        let verifier = HmacVerifier::parse("client1:s3cret");
+        let ts = now();
+        let message = HmacVerifier::string_to_sign("GET", "/js/a.js", Some("b=2&a=1%20x"), &ts, "nonce-0000000001", b"");
+        let signature = sign(b"s3cret", &message);
+
+        // 参数顺序和编码方式不影响签名
+        assert_eq!(
+            HmacVerifier::canonical_query(Some("b=2&a=1%20x")),
+            HmacVerifier::canonical_query(Some("a=1+x&b=2"))
+        );
+        assert!(verifier
+            .verify(&creds("client1", &ts, "nonce-0000000001", &signature), "GET", "/js/a.js", Some("b=3&a=1%20x"), b"")
+            .is_err());
+        assert!(verifier
+            .verify(&creds("client1", &ts, "nonce-0000000001", &signature), "GET", "/js/a.js", Some("a=1+x&b=2"), b"")
+            .is_ok());
+    }
+
+    #[test]
+    fn test_seen_nonces_expire() {
+        let mut seen = SeenNonces::default();
+        assert!(seen.insert("k", "n1", 100, 400));
+        assert!(!seen.insert("k", "n1", 200, 500));
+        assert!(seen.insert("k2", "n1", 200, 500));
+        // n1 过期后清理，可以再次使用
+        assert!(seen.insert("k", "n2", 401, 700));
+        assert!(seen.insert("k", "n1", 401, 701));
+        assert_eq!(seen.keys.len(), 4 - 1);
+    }
+}
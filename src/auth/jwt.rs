@@ -0,0 +1,131 @@
+use crate::auth::AuthIdentity;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode, decode_header};
+
+/// JWT Bearer Token 校验 - 单一职责：校验 HS256 / RS256 签名及标准声明
+#[derive(Clone, Default)]
+pub struct JwtVerifier {
+    hs256_key: Option<DecodingKey>,
+    rs256_key: Option<DecodingKey>,
+    issuer: Option<String>,
+    audience: Option<String>,
+}
+
+impl JwtVerifier {
+    /// 从本地文件加载密钥
+    pub fn from_files(
+        hs256_secret_file: Option<&str>,
+        rs256_public_key_file: Option<&str>,
+    ) -> Result<Self, String> {
+        let mut verifier = Self::default();
+        if let Some(path) = hs256_secret_file {
+            let secret = std::fs::read(path)
+                .map_err(|e| format!("Failed to read HS256 secret {}: {}", path, e))?;
+            verifier = verifier.with_hs256_secret(secret.trim_ascii_end());
+        }
+        if let Some(path) = rs256_public_key_file {
+            let pem = std::fs::read(path)
+                .map_err(|e| format!("Failed to read RS256 public key {}: {}", path, e))?;
+            let key = DecodingKey::from_rsa_pem(&pem)
+                .map_err(|e| format!("Invalid RS256 public key {}: {}", path, e))?;
+            verifier.rs256_key = Some(key);
+        }
+        Ok(verifier)
+    }
+
+    /// 设置 HS256 共享密钥
+    pub fn with_hs256_secret(mut self, secret: &[u8]) -> Self {
+        self.hs256_key = Some(DecodingKey::from_secret(secret));
+        self
+    }
+
+    /// 设置期望的 `iss`
+    pub fn with_issuer(mut self, issuer: Option<String>) -> Self {
+        self.issuer = issuer;
+        self
+    }
+
+    /// 设置期望的 `aud`
+    pub fn with_audience(mut self, audience: Option<String>) -> Self {
+        self.audience = audience;
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hs256_key.is_none() && self.rs256_key.is_none()
+    }
+
+    pub fn verify(&self, token: &str) -> Result<AuthIdentity, String> {
+        let header = decode_header(token).map_err(|e| format!("Invalid token: {}", e))?;
+        let key = match header.alg {
+            Algorithm::HS256 => self.hs256_key.as_ref(),
+            Algorithm::RS256 => self.rs256_key.as_ref(),
+            _ => None,
+        }
+        .ok_or_else(|| format!("Unsupported token algorithm: {:?}", header.alg))?;
+
+        let mut validation = Validation::new(header.alg);
+        if let Some(issuer) = &self.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        match &self.audience {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+
+        let data = decode::<serde_json::Value>(token, key, &validation)
+            .map_err(|e| format!("Invalid token: {}", e))?;
+        let subject = data
+            .claims
+            .get("sub")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+
+        Ok(AuthIdentity::new(&subject, "jwt", data.claims))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::{EncodingKey, Header, encode};
+    use serde_json::json;
+
+    fn token(secret: &[u8], claims: serde_json::Value) -> String {
+        encode(&Header::default(), &claims, &EncodingKey::from_secret(secret)).unwrap()
+    }
+
+    #[test]
+    fn test_jwt_hs256() {
+        let verifier = JwtVerifier::default().with_hs256_secret(b"jwt-secret");
+        let exp = jsonwebtoken::get_current_timestamp() + 60;
+
+        let identity = verifier
+            .verify(&token(b"jwt-secret", json!({"sub": "alice", "roles": ["admin"], "exp": exp})))
+            .unwrap();
+        assert_eq!(identity.subject, "alice");
+        assert!(identity.has_role("admin"));
+
+        assert!(verifier
+            .verify(&token(b"wrong", json!({"sub": "alice", "exp": exp})))
+            .is_err());
+        assert!(verifier
+            .verify(&token(b"jwt-secret", json!({"sub": "alice", "exp": 1})))
+            .is_err());
+    }
+
+    #[test]
+    fn test_jwt_issuer() {
+        let verifier = JwtVerifier::default()
+            .with_hs256_secret(b"jwt-secret")
+            .with_issuer(Some("ujs".to_string()));
+        let exp = jsonwebtoken::get_current_timestamp() + 60;
+
+        assert!(verifier
+            .verify(&token(b"jwt-secret", json!({"sub": "a", "iss": "ujs", "exp": exp})))
+            .is_ok());
+        assert!(verifier
+            .verify(&token(b"jwt-secret", json!({"sub": "a", "iss": "other", "exp": exp})))
+            .is_err());
+    }
+}
@@ -0,0 +1,285 @@
+pub mod api_key;
+pub mod hmac_signature;
+pub mod jwt;
+
+use crate::auth::api_key::ApiKeyVerifier;
+use crate::auth::hmac_signature::{HmacCredentials, HmacVerifier};
+use crate::auth::jwt::JwtVerifier;
+use crate::js_bridge::models::JsonRpcError;
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{StatusCode, header, request::Parts},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// 已认证的身份，作为 `request.user` 暴露给脚本
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct AuthIdentity {
+    /// 主体标识（API Key 对应的用户、HMAC key id 或 JWT `sub`）
+    pub subject: String,
+    /// 认证方式：api_key / hmac / jwt
+    pub scheme: String,
+    /// JWT 声明（其他方式为 null）
+    pub claims: serde_json::Value,
+}
+
+impl AuthIdentity {
+    pub fn new(subject: &str, scheme: &str, claims: serde_json::Value) -> Self {
+        Self {
+            subject: subject.to_string(),
+            scheme: scheme.to_string(),
+            claims,
+        }
+    }
+
+    /// 判断 JWT 声明中的 `role` / `roles` 是否包含指定角色
+    pub fn has_role(&self, role: &str) -> bool {
+        match (self.claims.get("roles"), self.claims.get("role")) {
+            (Some(serde_json::Value::Array(roles)), _) => roles.iter().any(|r| r == role),
+            (_, Some(serde_json::Value::String(r))) => r == role,
+            _ => false,
+        }
+    }
+}
+
+/// JSON-RPC 方法访问控制列表
+///
+/// 键为方法名（支持 `user.*` 前缀通配与 `*`），值为允许的主体列表：
+/// 具体 subject、`role:<name>` 或 `*`（任意已认证身份）。未匹配任何规则的方法默认放行。
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RpcAcl(HashMap<String, Vec<String>>);
+
+impl RpcAcl {
+    /// 从 JSON 文件加载
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read RPC ACL {}: {}", path, e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Invalid RPC ACL {}: {}", path, e))
+    }
+
+    /// 查找与方法匹配的规则（精确匹配优先，其次最长前缀）
+    fn rule_for(&self, method: &str) -> Option<&Vec<String>> {
+        if let Some(rule) = self.0.get(method) {
+            return Some(rule);
+        }
+        self.0
+            .iter()
+            .filter(|(pattern, _)| match pattern.strip_suffix('*') {
+                Some(prefix) => method.starts_with(prefix),
+                None => false,
+            })
+            .max_by_key(|(pattern, _)| pattern.len())
+            .map(|(_, rule)| rule)
+    }
+
+    /// 检查身份是否可以调用方法
+    pub fn check(&self, method: &str, identity: Option<&AuthIdentity>) -> Result<(), JsonRpcError> {
+        let Some(allowed) = self.rule_for(method) else {
+            return Ok(());
+        };
+        let permitted = identity.is_some_and(|id| {
+            allowed.iter().any(|principal| match principal.strip_prefix("role:") {
+                Some(role) => id.has_role(role),
+                None => principal == "*" || *principal == id.subject,
+            })
+        });
+        if permitted {
+            Ok(())
+        } else {
+            Err(JsonRpcError::forbidden(method))
+        }
+    }
+}
+
+/// 认证配置
+#[derive(Clone, Default)]
+pub struct AuthConfig {
+    /// 是否启用认证（关闭时所有请求直接放行）
+    pub enabled: bool,
+    pub api_keys: ApiKeyVerifier,
+    pub hmac: HmacVerifier,
+    pub jwt: JwtVerifier,
+    pub rpc_acl: Arc<RpcAcl>,
+}
+
+impl AuthConfig {
+    /// 从环境变量读取配置
+    pub fn from_env() -> Result<Self, String> {
+        dotenvy::dotenv().ok();
+        let var = |key: &str| std::env::var(key).ok().filter(|v| !v.is_empty());
+
+        let jwt = JwtVerifier::from_files(
+            var("AUTH_JWT_HS256_SECRET_FILE").as_deref(),
+            var("AUTH_JWT_RS256_PUBLIC_KEY_FILE").as_deref(),
+        )?
+        .with_issuer(var("AUTH_JWT_ISSUER"))
+        .with_audience(var("AUTH_JWT_AUDIENCE"));
+
+        let rpc_acl = match var("AUTH_RPC_ACL_FILE") {
+            Some(path) => RpcAcl::from_file(&path)?,
+            None => RpcAcl::default(),
+        };
+
+        let config = Self {
+            enabled: var("AUTH_ENABLED").is_some_and(|v| v == "true" || v == "1"),
+            api_keys: ApiKeyVerifier::parse(&var("AUTH_API_KEYS").unwrap_or_default()),
+            hmac: HmacVerifier::parse(&var("AUTH_HMAC_KEYS").unwrap_or_default()),
+            jwt,
+            rpc_acl: Arc::new(rpc_acl),
+        };
+        if config.enabled && config.api_keys.is_empty() && config.hmac.is_empty() && config.jwt.is_empty() {
+            tracing::warn!("AUTH_ENABLED 已开启但未配置任何凭证，所有请求都会被拒绝");
+        }
+        Ok(config)
+    }
+
+    /// 校验请求凭证，HMAC 校验需要读取请求体，因此返回重建后的 body
+    pub async fn authenticate(&self, parts: &Parts, body: Body) -> Result<(AuthIdentity, Body), String> {
+        let header = |name: &str| parts.headers.get(name).and_then(|v| v.to_str().ok());
+
+        // 1. Bearer Token（浏览器 WebSocket 无法设置请求头，允许使用 access_token 查询参数）
+        let bearer = header(header::AUTHORIZATION.as_str())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(str::to_string)
+            .or_else(|| query_param(parts.uri.query(), "access_token"));
+        if let Some(token) = bearer {
+            if self.jwt.is_empty() {
+                return Err("JWT authentication is not configured".to_string());
+            }
+            return self.jwt.verify(&token).map(|id| (id, body));
+        }
+
+        // 2. 静态 API Key
+        if let Some(key) = header("x-api-key") {
+            return self
+                .api_keys
+                .verify(key)
+                .map(|id| (id, body))
+                .ok_or_else(|| "Invalid API key".to_string());
+        }
+
+        // 3. HMAC 签名
+        if let (Some(key_id), Some(timestamp), Some(signature)) =
+            (header("x-key-id"), header("x-timestamp"), header("x-signature"))
+        {
+            let nonce = header("x-nonce").ok_or_else(|| "Missing X-Nonce".to_string())?;
+            let bytes = axum::body::to_bytes(body, 1024 * 1024)
+                .await
+                .map_err(|e| format!("Failed to read body: {}", e))?;
+            let credentials = HmacCredentials {
+                key_id,
+                timestamp,
+                nonce,
+                signature,
+            };
+            let identity = self.hmac.verify(
+                &credentials,
+                parts.method.as_str(),
+                parts.uri.path(),
+                parts.uri.query(),
+                &bytes,
+            )?;
+            return Ok((identity, Body::from(bytes)));
+        }
+
+        Err("Missing credentials".to_string())
+    }
+}
+
+/// 认证中间件 - 校验通过后将 `AuthIdentity` 和 `RpcAcl` 写入请求扩展
+pub async fn require_auth(
+    State(config): State<Arc<AuthConfig>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if !config.enabled {
+        return next.run(req).await;
+    }
+
+    let (mut parts, body) = req.into_parts();
+    match config.authenticate(&parts, body).await {
+        Ok((identity, body)) => {
+            tracing::debug!("authenticated {} via {}", identity.subject, identity.scheme);
+            parts.extensions.insert(identity);
+            parts.extensions.insert(config.rpc_acl.clone());
+            next.run(Request::from_parts(parts, body)).await
+        }
+        Err(msg) => {
+            tracing::warn!("authentication failed for {}: {}", parts.uri.path(), msg);
+            (
+                StatusCode::UNAUTHORIZED,
+                [(header::WWW_AUTHENTICATE, "Bearer")],
+                axum::Json(serde_json::json!({ "error": msg })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// 读取查询参数，按 `application/x-www-form-urlencoded` 解码
+fn query_param(query: Option<&str>, name: &str) -> Option<String> {
+    form_urlencoded::parse(query?.as_bytes()).find_map(|(k, v)| (k == name).then(|| v.into_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Request as HttpRequest;
+    use serde_json::json;
+
+    fn parts(req: HttpRequest<()>) -> Parts {
+        req.into_parts().0
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_api_key() {
+        let config = AuthConfig {
+            enabled: true,
+            api_keys: ApiKeyVerifier::parse("k1:alice"),
+            ..Default::default()
+        };
+
+        let ok = parts(HttpRequest::get("/js/a.js").header("x-api-key", "k1").body(()).unwrap());
+        let (identity, _) = config.authenticate(&ok, Body::empty()).await.unwrap();
+        assert_eq!(identity.subject, "alice");
+        assert_eq!(identity.scheme, "api_key");
+
+        let bad = parts(HttpRequest::get("/js/a.js").header("x-api-key", "nope").body(()).unwrap());
+        assert!(config.authenticate(&bad, Body::empty()).await.is_err());
+
+        let missing = parts(HttpRequest::get("/js/a.js").body(()).unwrap());
+        assert!(config.authenticate(&missing, Body::empty()).await.is_err());
+    }
+
+    #[test]
+    fn test_query_param_decodes() {
+        assert_eq!(query_param(Some("a=1&access_token=x%2By%3D"), "access_token").as_deref(), Some("x+y="));
+        assert_eq!(query_param(Some("access_token=a+b"), "access_token").as_deref(), Some("a b"));
+        assert_eq!(query_param(None, "access_token"), None);
+    }
+
+    #[test]
+    fn test_rpc_acl() {
+        let acl: RpcAcl = serde_json::from_value(json!({
+            "admin.*": ["role:admin"],
+            "admin.stats": ["bob"],
+            "add": ["*"]
+        }))
+        .unwrap();
+        let alice = AuthIdentity::new("alice", "jwt", json!({"roles": ["admin"]}));
+        let bob = AuthIdentity::new("bob", "api_key", serde_json::Value::Null);
+
+        assert!(acl.check("admin.users", Some(&alice)).is_ok());
+        assert_eq!(acl.check("admin.users", Some(&bob)).unwrap_err().code, -32003);
+        assert!(acl.check("admin.stats", Some(&bob)).is_ok());
+        assert!(acl.check("admin.stats", Some(&alice)).is_err());
+        assert!(acl.check("add", Some(&bob)).is_ok());
+        assert!(acl.check("add", None).is_err());
+        assert!(acl.check("multiply", None).is_ok());
+    }
+}
@@ -0,0 +1,266 @@
+use diesel::pg::{Pg, PgRowByRowLoadingMode};
+use diesel::connection::DefaultLoadingMode;
+use diesel::prelude::*;
+use diesel::r2d2::{self, ConnectionManager, PooledConnection, R2D2Connection};
+use diesel::row::{Field, NamedRow, Row};
+use diesel::sqlite::{Sqlite, SqliteType};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fmt;
+use std::time::Duration;
+
+/// 动态行 - 用于存储数据库查询结果
+///
+/// 列名到 JSON 值的映射，每种后端各自实现 `QueryableByName`，保证脚本拿到的结果一致。
+#[derive(Serialize, Deserialize)]
+pub struct DynamicRow(pub Map<String, Value>);
+
+impl diesel::deserialize::QueryableByName<Pg> for DynamicRow {
+    fn build<'a>(
+        row: &impl NamedRow<'a, Pg>,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        use diesel::deserialize::FromSql;
+        let mut map = Map::new();
+        let column_count = row.field_count();
+
+        for i in 0..column_count {
+            let field = Row::get(row, i).ok_or_else(|| "Failed to get field")?;
+            let name = field
+                .field_name()
+                .ok_or_else(|| "Failed to get column name")?;
+
+            let value = if field.is_null() {
+                Value::Null
+            } else {
+                let raw_value = field.value().unwrap();
+                // 尝试多种类型，通过顺序保证优先匹配
+                if let Ok(v) = FromSql::<diesel::sql_types::Integer, Pg>::from_sql(raw_value) {
+                    let v: i32 = v;
+                    Value::Number(serde_json::Number::from(v))
+                } else if let Ok(v) = FromSql::<diesel::sql_types::Text, Pg>::from_sql(raw_value) {
+                    let v: String = v;
+                    Value::String(v)
+                } else if let Ok(v) = FromSql::<diesel::sql_types::BigInt, Pg>::from_sql(raw_value)
+                {
+                    let v: i64 = v;
+                    Value::Number(serde_json::Number::from(v))
+                } else if let Ok(v) = FromSql::<diesel::sql_types::Double, Pg>::from_sql(raw_value)
+                {
+                    let v: f64 = v;
+                    serde_json::Number::from_f64(v)
+                        .map(Value::Number)
+                        .unwrap_or(Value::Null)
+                } else if let Ok(v) = FromSql::<diesel::sql_types::Bool, Pg>::from_sql(raw_value) {
+                    let v: bool = v;
+                    Value::Bool(v)
+                } else {
+                    String::from_utf8(raw_value.as_bytes().to_vec())
+                        .map(Value::String)
+                        .unwrap_or_else(|_| {
+                            Value::String(format!("Binary: {} bytes", raw_value.as_bytes().len()))
+                        })
+                }
+            };
+
+            map.insert(name.to_string(), value);
+        }
+        Ok(DynamicRow(map))
+    }
+}
+
+impl diesel::deserialize::QueryableByName<Sqlite> for DynamicRow {
+    fn build<'a>(
+        row: &impl NamedRow<'a, Sqlite>,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let mut map = Map::new();
+
+        for i in 0..row.field_count() {
+            let field = Row::get(row, i).ok_or("Failed to get field")?;
+            let name = field.field_name().ok_or("Failed to get column name")?;
+
+            // SQLite 的值自带存储类型，按存储类型直接读取
+            let value = match field.value() {
+                None => Value::Null,
+                Some(mut raw_value) => match raw_value.value_type() {
+                    Some(SqliteType::Long | SqliteType::Integer | SqliteType::SmallInt) => {
+                        Value::Number(serde_json::Number::from(raw_value.read_long()))
+                    }
+                    Some(SqliteType::Double | SqliteType::Float) => {
+                        serde_json::Number::from_f64(raw_value.read_double())
+                            .map(Value::Number)
+                            .unwrap_or(Value::Null)
+                    }
+                    Some(SqliteType::Binary) => {
+                        Value::String(format!("Binary: {} bytes", raw_value.read_blob().len()))
+                    }
+                    _ => Value::String(raw_value.read_text().to_string()),
+                },
+            };
+
+            map.insert(name.to_string(), value);
+        }
+        Ok(DynamicRow(map))
+    }
+}
+
+/// 数据库后端
+///
+/// 每种数据库实现原始 SQL 的执行和结果行转换，[`DbPool`] 按 URL scheme 选择后端。
+pub trait DbBackend: R2D2Connection + 'static {
+    /// 写入 tracing span 的 `db.system`
+    const SYSTEM: &'static str;
+
+    /// 执行语句，返回受影响的行数
+    fn execute_sql(&mut self, sql: &str) -> QueryResult<usize>;
+
+    /// 执行查询，返回动态行；`limit` 为 `Some(n)` 时最多读取 n 行，其余行不做转换也不缓存
+    fn query_rows(&mut self, sql: &str, limit: Option<usize>) -> QueryResult<Vec<DynamicRow>>;
+}
+
+impl DbBackend for PgConnection {
+    const SYSTEM: &'static str = "postgresql";
+
+    fn execute_sql(&mut self, sql: &str) -> QueryResult<usize> {
+        diesel::sql_query(sql).execute(self)
+    }
+
+    fn query_rows(&mut self, sql: &str, limit: Option<usize>) -> QueryResult<Vec<DynamicRow>> {
+        match limit {
+            // 默认模式下 libpq 会先缓存全部结果，逐行模式只保留当前行
+            Some(limit) => diesel::sql_query(sql)
+                .load_iter::<DynamicRow, PgRowByRowLoadingMode>(self)?
+                .take(limit)
+                .collect(),
+            None => diesel::sql_query(sql).load(self),
+        }
+    }
+}
+
+impl DbBackend for SqliteConnection {
+    const SYSTEM: &'static str = "sqlite";
+
+    fn execute_sql(&mut self, sql: &str) -> QueryResult<usize> {
+        diesel::sql_query(sql).execute(self)
+    }
+
+    fn query_rows(&mut self, sql: &str, limit: Option<usize>) -> QueryResult<Vec<DynamicRow>> {
+        match limit {
+            Some(limit) => diesel::sql_query(sql)
+                .load_iter::<DynamicRow, DefaultLoadingMode>(self)?
+                .take(limit)
+                .collect(),
+            None => diesel::sql_query(sql).load(self),
+        }
+    }
+}
+
+/// 对每种后端展开同一段代码，`$inner` 在各分支中绑定到具体类型的连接池或连接
+macro_rules! with_backend {
+    ($value:expr, $enum:ident, $inner:pat => $body:expr) => {
+        match $value {
+            $enum::Postgres($inner) => $body,
+            $enum::Sqlite($inner) => $body,
+        }
+    };
+}
+pub(crate) use with_backend;
+
+pub type Pool<B> = r2d2::Pool<ConnectionManager<B>>;
+
+/// 数据库连接池
+#[derive(Clone)]
+pub enum DbPool {
+    Postgres(Pool<PgConnection>),
+    Sqlite(Pool<SqliteConnection>),
+}
+
+/// 从连接池取出的连接
+pub enum DbConnection {
+    Postgres(PooledConnection<ConnectionManager<PgConnection>>),
+    Sqlite(PooledConnection<ConnectionManager<SqliteConnection>>),
+}
+
+/// 数据库错误
+#[derive(Debug)]
+pub enum DbError {
+    /// 取不到连接（数据库不可用或连接池耗尽）
+    Unavailable(r2d2::PoolError),
+    /// SQL 执行失败
+    Query(diesel::result::Error),
+    /// `db.query` 返回的行数超过 `DB_QUERY_MAX_ROWS`
+    TooManyRows(usize),
+}
+
+impl fmt::Display for DbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DbError::Unavailable(e) => write!(f, "database unavailable: {}", e),
+            DbError::Query(e) => write!(f, "SQL execution failed: {}", e),
+            DbError::TooManyRows(max) => write!(
+                f,
+                "query returned more than {} rows (DB_QUERY_MAX_ROWS), use db.cursor() for large result sets",
+                max
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DbError {}
+
+impl DbPool {
+    /// 后端名称（`postgresql` / `sqlite`）
+    pub fn system(&self) -> &'static str {
+        match self {
+            DbPool::Postgres(_) => PgConnection::SYSTEM,
+            DbPool::Sqlite(_) => SqliteConnection::SYSTEM,
+        }
+    }
+
+    /// 取连接，最多等待连接池配置的 `connection_timeout`
+    pub fn get(&self) -> Result<DbConnection, DbError> {
+        match self {
+            DbPool::Postgres(pool) => pool.get().map(DbConnection::Postgres),
+            DbPool::Sqlite(pool) => pool.get().map(DbConnection::Sqlite),
+        }
+        .map_err(DbError::Unavailable)
+    }
+
+    /// 取连接，最多等待 `timeout`
+    pub fn get_timeout(&self, timeout: Duration) -> Result<DbConnection, DbError> {
+        match self {
+            DbPool::Postgres(pool) => pool.get_timeout(timeout).map(DbConnection::Postgres),
+            DbPool::Sqlite(pool) => pool.get_timeout(timeout).map(DbConnection::Sqlite),
+        }
+        .map_err(DbError::Unavailable)
+    }
+
+    pub fn state(&self) -> r2d2::State {
+        with_backend!(self, DbPool, pool => pool.state())
+    }
+
+    pub fn max_size(&self) -> u32 {
+        with_backend!(self, DbPool, pool => pool.max_size())
+    }
+}
+
+impl DbConnection {
+    pub fn execute(&mut self, sql: &str) -> Result<usize, DbError> {
+        with_backend!(self, DbConnection, conn => conn.execute_sql(sql)).map_err(DbError::Query)
+    }
+
+    pub fn query(&mut self, sql: &str) -> Result<Vec<DynamicRow>, DbError> {
+        with_backend!(self, DbConnection, conn => conn.query_rows(sql, None)).map_err(DbError::Query)
+    }
+
+    /// 执行查询，结果超过 `max_rows` 行时返回 [`DbError::TooManyRows`]
+    ///
+    /// 只转换前 `max_rows + 1` 行；PostgreSQL 使用逐行模式，其余行从连接上丢弃而不在内存中缓存。
+    pub fn query_max(&mut self, sql: &str, max_rows: usize) -> Result<Vec<DynamicRow>, DbError> {
+        let rows = with_backend!(self, DbConnection, conn => conn.query_rows(sql, Some(max_rows + 1)))
+            .map_err(DbError::Query)?;
+        if rows.len() > max_rows {
+            return Err(DbError::TooManyRows(max_rows));
+        }
+        Ok(rows)
+    }
+}
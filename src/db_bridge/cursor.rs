@@ -0,0 +1,226 @@
+use super::backend::{DbError, DbPool, DynamicRow, Pool};
+use diesel::backend::Backend;
+use diesel::connection::{AnsiTransactionManager, TransactionManager};
+use diesel::pg::PgRowByRowLoadingMode;
+use diesel::prelude::*;
+use diesel::query_builder::{BoxedSqlQuery, SqlQuery};
+use diesel::r2d2::{ConnectionManager, PooledConnection};
+use diesel::serialize::ToSql;
+use diesel::sql_types::{BigInt, Bool, Double, HasSqlType, Nullable, Text};
+use serde_json::Value;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 游标名称序号，同一连接上的游标名不能重复
+static NEXT_CURSOR_ID: AtomicU64 = AtomicU64::new(0);
+
+/// 按 JSON 类型绑定位置参数（`$1`、`$2` …）
+///
+/// 整数绑定为 `BIGINT`，小数为 `DOUBLE`，布尔为 `BOOL`，`null` 为 `NULL`，
+/// 字符串原样绑定为 `TEXT`，数组和对象序列化为 JSON 文本。
+pub fn bind_params<'f, DB>(
+    mut query: BoxedSqlQuery<'f, DB, SqlQuery>,
+    params: &[Value],
+) -> BoxedSqlQuery<'f, DB, SqlQuery>
+where
+    DB: Backend
+        + HasSqlType<Text>
+        + HasSqlType<BigInt>
+        + HasSqlType<Double>
+        + HasSqlType<Bool>
+        + HasSqlType<Nullable<Text>>,
+    String: ToSql<Text, DB>,
+    i64: ToSql<BigInt, DB>,
+    f64: ToSql<Double, DB>,
+    bool: ToSql<Bool, DB>,
+    Option<String>: ToSql<Nullable<Text>, DB>,
+{
+    for param in params {
+        query = match param {
+            Value::Null => query.bind::<Nullable<Text>, _>(None::<String>),
+            Value::Bool(b) => query.bind::<Bool, _>(*b),
+            Value::Number(n) => match n.as_i64() {
+                Some(i) => query.bind::<BigInt, _>(i),
+                None => query.bind::<Double, _>(n.as_f64().unwrap_or(f64::NAN)),
+            },
+            Value::String(s) => query.bind::<Text, _>(s.clone()),
+            other => query.bind::<Text, _>(other.to_string()),
+        };
+    }
+    query
+}
+
+/// 去掉末尾的分号，便于把查询嵌入 `DECLARE` 或子查询
+fn trim_statement(sql: &str) -> &str {
+    sql.trim().trim_end_matches(';').trim_end()
+}
+
+/// 查询游标，分批读取结果集，避免一次把全部行读入内存
+///
+/// PostgreSQL 使用服务端游标（在事务中 `DECLARE` / `FETCH`），游标存续期间独占一个连接；
+/// SQLite 每批用 `LIMIT` / `OFFSET` 重新查询，不占用连接。
+pub enum DbCursor {
+    Postgres(PgCursor),
+    Sqlite(SqliteCursor),
+}
+
+impl DbPool {
+    /// 打开游标，`params` 按位置绑定到 `$1`、`$2` …
+    pub fn open_cursor(&self, sql: &str, params: Vec<Value>) -> Result<DbCursor, DbError> {
+        match self {
+            DbPool::Postgres(pool) => {
+                let conn = pool.get().map_err(DbError::Unavailable)?;
+                PgCursor::open(conn, sql, &params).map(DbCursor::Postgres)
+            }
+            DbPool::Sqlite(pool) => Ok(DbCursor::Sqlite(SqliteCursor {
+                pool: pool.clone(),
+                sql: trim_statement(sql).to_string(),
+                params,
+                offset: 0,
+                done: false,
+            })),
+        }
+    }
+}
+
+impl DbCursor {
+    /// 读取下一批，最多 `batch_size` 行；返回空数组表示已读完
+    pub fn fetch(&mut self, batch_size: usize) -> Result<Vec<DynamicRow>, DbError> {
+        match self {
+            DbCursor::Postgres(cursor) => cursor.fetch(batch_size),
+            DbCursor::Sqlite(cursor) => cursor.fetch(batch_size),
+        }
+    }
+
+    /// 关闭游标并释放连接
+    pub fn close(self) -> Result<(), DbError> {
+        match self {
+            DbCursor::Postgres(cursor) => cursor.close(),
+            DbCursor::Sqlite(_) => Ok(()),
+        }
+    }
+}
+
+pub struct PgCursor {
+    conn: PooledConnection<ConnectionManager<PgConnection>>,
+    name: String,
+    /// 事务尚未提交；未正常关闭时在 drop 中回滚
+    open: bool,
+}
+
+impl PgCursor {
+    fn open(
+        mut conn: PooledConnection<ConnectionManager<PgConnection>>,
+        sql: &str,
+        params: &[Value],
+    ) -> Result<Self, DbError> {
+        let name = format!("ujs_cursor_{}", NEXT_CURSOR_ID.fetch_add(1, Ordering::Relaxed));
+        AnsiTransactionManager::begin_transaction(&mut *conn).map_err(DbError::Query)?;
+        let mut cursor = Self { conn, name, open: true };
+
+        let declare = diesel::sql_query(format!(
+            "DECLARE {} NO SCROLL CURSOR FOR {}",
+            cursor.name,
+            trim_statement(sql)
+        ))
+        .into_boxed();
+        bind_params(declare, params)
+            .execute(&mut *cursor.conn)
+            .map_err(DbError::Query)?;
+        Ok(cursor)
+    }
+
+    fn fetch(&mut self, batch_size: usize) -> Result<Vec<DynamicRow>, DbError> {
+        diesel::sql_query(format!("FETCH {} FROM {}", batch_size, self.name))
+            .load_iter::<DynamicRow, PgRowByRowLoadingMode>(&mut *self.conn)
+            .and_then(|rows| rows.collect())
+            .map_err(DbError::Query)
+    }
+
+    fn close(mut self) -> Result<(), DbError> {
+        self.open = false;
+        diesel::sql_query(format!("CLOSE {}", self.name))
+            .execute(&mut *self.conn)
+            .map_err(DbError::Query)?;
+        AnsiTransactionManager::commit_transaction(&mut *self.conn).map_err(DbError::Query)
+    }
+}
+
+impl Drop for PgCursor {
+    fn drop(&mut self) {
+        if self.open {
+            let _ = AnsiTransactionManager::rollback_transaction(&mut *self.conn);
+        }
+    }
+}
+
+pub struct SqliteCursor {
+    pool: Pool<SqliteConnection>,
+    sql: String,
+    params: Vec<Value>,
+    offset: usize,
+    done: bool,
+}
+
+impl SqliteCursor {
+    fn fetch(&mut self, batch_size: usize) -> Result<Vec<DynamicRow>, DbError> {
+        if self.done {
+            return Ok(Vec::new());
+        }
+        let mut conn = self.pool.get().map_err(DbError::Unavailable)?;
+        let query = diesel::sql_query(format!(
+            "SELECT * FROM ({}) LIMIT {} OFFSET {}",
+            self.sql, batch_size, self.offset
+        ))
+        .into_boxed();
+        let rows: Vec<DynamicRow> = bind_params(query, &self.params)
+            .load(&mut *conn)
+            .map_err(DbError::Query)?;
+        self.offset += rows.len();
+        self.done = rows.len() < batch_size;
+        Ok(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_bridge::build_pool;
+
+    #[test]
+    fn test_trim_statement() {
+        assert_eq!(trim_statement(" SELECT 1; \n"), "SELECT 1");
+        assert_eq!(trim_statement("SELECT 1"), "SELECT 1");
+    }
+
+    #[test]
+    fn test_sqlite_cursor_batches() {
+        let pool = build_pool("sqlite::memory:".to_string()).unwrap();
+        {
+            let mut conn = pool.get().unwrap();
+            conn.execute("CREATE TABLE cursor_test (n INTEGER, label TEXT)").unwrap();
+            for n in 0..5 {
+                conn.execute(&format!("INSERT INTO cursor_test VALUES ({}, 'row{}')", n, n))
+                    .unwrap();
+            }
+        }
+
+        let sql = "SELECT n, label FROM cursor_test WHERE n >= $1 ORDER BY n;";
+        let mut cursor = pool.open_cursor(sql, vec![serde_json::json!(1)]).unwrap();
+        let batch = cursor.fetch(3).unwrap();
+        assert_eq!(batch.len(), 3);
+        assert_eq!(batch[0].0["n"], 1);
+        assert_eq!(batch[2].0["label"], "row3");
+        assert_eq!(cursor.fetch(3).unwrap().len(), 1);
+        assert!(cursor.fetch(3).unwrap().is_empty());
+        cursor.close().unwrap();
+    }
+
+    #[test]
+    fn test_query_max_rows() {
+        let pool = build_pool("sqlite::memory:".to_string()).unwrap();
+        let mut conn = pool.get().unwrap();
+        let sql = "SELECT 1 AS n UNION ALL SELECT 2 UNION ALL SELECT 3";
+        assert_eq!(conn.query_max(sql, 3).unwrap().len(), 3);
+        assert!(matches!(conn.query_max(sql, 2), Err(DbError::TooManyRows(2))));
+    }
+}
@@ -0,0 +1,291 @@
+use super::{DbConnection, DbPool, with_backend};
+use diesel::backend::Backend;
+use diesel::migration::{self, MigrationSource};
+use diesel_migrations::{FileBasedMigrations, MigrationHarness};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::env;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// 迁移目录，`MIGRATIONS_DIR` 可覆盖
+///
+/// 每个迁移一个子目录，目录名为 `<版本>_<名称>`，包含 `up.sql` 和 `down.sql`。
+pub fn migrations_dir() -> String {
+    env::var("MIGRATIONS_DIR").unwrap_or_else(|_| "./migrations".to_string())
+}
+
+/// 是否在服务启动时执行未应用的迁移（`MIGRATE_ON_STARTUP=true`）
+pub fn run_on_startup() -> bool {
+    env::var("MIGRATE_ON_STARTUP")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false)
+}
+
+/// 单个迁移的状态
+#[derive(Debug, Serialize)]
+pub struct MigrationStatus {
+    /// 目录名，如 `2025-01-01-000000_create_users`；目录已删除的已应用迁移只有版本号
+    pub name: String,
+    pub version: String,
+    pub applied: bool,
+}
+
+fn load(dir: &str) -> Result<FileBasedMigrations, String> {
+    FileBasedMigrations::from_path(dir)
+        .map_err(|e| format!("failed to load migrations from {}: {}", dir, e))
+}
+
+/// 执行所有未应用的迁移，返回本次应用的版本
+///
+/// 应用记录保存在 diesel 的 `__diesel_schema_migrations` 表中，每个迁移在单独的事务中执行。
+pub fn run_pending(conn: &mut DbConnection, dir: &str) -> Result<Vec<String>, String> {
+    let source = load(dir)?;
+    with_backend!(conn, DbConnection, conn => {
+        conn.run_pending_migrations(source)
+            .map(|versions| versions.iter().map(ToString::to_string).collect())
+    })
+    .map_err(|e| format!("migration failed: {}", e))
+}
+
+/// 按应用顺序倒序回滚最近 `steps` 个迁移，返回回滚的版本
+pub fn revert(conn: &mut DbConnection, dir: &str, steps: usize) -> Result<Vec<String>, String> {
+    let source = load(dir)?;
+    with_backend!(conn, DbConnection, conn => revert_last(&mut **conn, &source, steps))
+        .map_err(|e| format!("rollback failed: {}", e))
+}
+
+/// 迁移目录中的全部迁移及是否已应用
+pub fn status(conn: &mut DbConnection, dir: &str) -> Result<Vec<MigrationStatus>, String> {
+    let source = load(dir)?;
+    with_backend!(conn, DbConnection, conn => status_of(&mut **conn, &source))
+        .map_err(|e| format!("failed to read migration status: {}", e))
+}
+
+/// 启动时执行迁移，见 [`run_on_startup`]
+pub fn migrate_on_startup(pool: &DbPool) -> Result<Vec<String>, String> {
+    let mut conn = pool.get().map_err(|e| e.to_string())?;
+    run_pending(&mut conn, &migrations_dir())
+}
+
+/// 启动迁移尚未完成时的原因；未启用或已完成时为 `None`
+static STARTUP_PENDING: Mutex<Option<String>> = Mutex::new(None);
+
+/// 启动迁移是否已完成，供就绪检查使用
+pub fn startup_status() -> Result<(), String> {
+    match STARTUP_PENDING.lock().unwrap().clone() {
+        Some(reason) => Err(reason),
+        None => Ok(()),
+    }
+}
+
+/// 在后台执行启动迁移
+///
+/// 数据库暂时不可用时服务照常启动，按与连接监控相同的退避间隔重试，直到迁移成功；
+/// 完成之前 [`startup_status`] 返回错误，`/readyz` 保持 503。
+pub fn spawn_on_startup(pool: DbPool) -> tokio::task::JoinHandle<()> {
+    *STARTUP_PENDING.lock().unwrap() = Some("database migrations have not run yet".to_string());
+    tokio::spawn(async move {
+        let mut retry_delay = Duration::ZERO;
+        loop {
+            let migrate_pool = pool.clone();
+            let result = tokio::task::spawn_blocking(move || migrate_on_startup(&migrate_pool))
+                .await
+                .unwrap_or_else(|e| Err(format!("migration task panicked: {}", e)));
+            match result {
+                Ok(applied) => {
+                    tracing::info!("applied {} database migrations", applied.len());
+                    *STARTUP_PENDING.lock().unwrap() = None;
+                    return;
+                }
+                Err(e) => {
+                    retry_delay = super::next_retry_delay(retry_delay);
+                    tracing::error!("failed to run database migrations, retrying in {:?}: {}", retry_delay, e);
+                    *STARTUP_PENDING.lock().unwrap() = Some(format!("database migrations failed: {}", e));
+                    tokio::time::sleep(retry_delay).await;
+                }
+            }
+        }
+    })
+}
+
+fn revert_last<C, DB>(
+    conn: &mut C,
+    source: &FileBasedMigrations,
+    steps: usize,
+) -> migration::Result<Vec<String>>
+where
+    DB: Backend,
+    C: MigrationHarness<DB>,
+    FileBasedMigrations: MigrationSource<DB>,
+{
+    let mut reverted = Vec::new();
+    for _ in 0..steps {
+        if conn.applied_migrations()?.is_empty() {
+            break;
+        }
+        reverted.push(conn.revert_last_migration(source.clone())?.to_string());
+    }
+    Ok(reverted)
+}
+
+fn status_of<C, DB>(
+    conn: &mut C,
+    source: &FileBasedMigrations,
+) -> migration::Result<Vec<MigrationStatus>>
+where
+    DB: Backend,
+    C: MigrationHarness<DB>,
+    FileBasedMigrations: MigrationSource<DB>,
+{
+    let mut applied: HashSet<String> = conn
+        .applied_migrations()?
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+
+    let mut statuses: Vec<MigrationStatus> = MigrationSource::<DB>::migrations(source)?
+        .iter()
+        .map(|m| {
+            let version = m.name().version().to_string();
+            MigrationStatus {
+                name: m.name().to_string(),
+                applied: applied.remove(&version),
+                version,
+            }
+        })
+        .collect();
+
+    // 已应用但目录中已找不到的迁移
+    statuses.extend(applied.into_iter().map(|version| MigrationStatus {
+        name: version.clone(),
+        version,
+        applied: true,
+    }));
+    statuses.sort_by(|a, b| a.version.cmp(&b.version));
+    Ok(statuses)
+}
+
+/// `migrate` 子命令
+///
+/// ```text
+/// ujs-web-svr migrate [up]       执行未应用的迁移
+/// ujs-web-svr migrate status     列出迁移及是否已应用
+/// ujs-web-svr migrate down [N]   回滚最近 N 个迁移（默认 1）
+/// ```
+pub fn run_cli(args: &[String]) -> Result<(), String> {
+    let dir = migrations_dir();
+    let pool = super::establish_connection_pool();
+    let mut conn = pool.get().map_err(|e| e.to_string())?;
+
+    match args.first().map(String::as_str).unwrap_or("up") {
+        "up" => {
+            let applied = run_pending(&mut conn, &dir)?;
+            if applied.is_empty() {
+                println!("database is up to date");
+            }
+            for version in applied {
+                println!("applied {}", version);
+            }
+        }
+        "status" => {
+            for migration in status(&mut conn, &dir)? {
+                let mark = if migration.applied { "applied" } else { "pending" };
+                println!("{:<8} {}", mark, migration.name);
+            }
+        }
+        "down" => {
+            let steps = match args.get(1) {
+                Some(n) => n
+                    .parse()
+                    .map_err(|_| format!("invalid number of migrations to revert: {}", n))?,
+                None => 1,
+            };
+            let reverted = revert(&mut conn, &dir, steps)?;
+            if reverted.is_empty() {
+                println!("no migrations to revert");
+            }
+            for version in reverted {
+                println!("reverted {}", version);
+            }
+        }
+        other => {
+            return Err(format!(
+                "unknown migrate command \"{}\", expected up, status or down [N]",
+                other
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_bridge::build_pool;
+    use std::fs;
+
+    fn write_migration(dir: &std::path::Path, name: &str, up: &str, down: &str) {
+        let path = dir.join(name);
+        fs::create_dir_all(&path).unwrap();
+        fs::write(path.join("up.sql"), up).unwrap();
+        fs::write(path.join("down.sql"), down).unwrap();
+    }
+
+    #[test]
+    fn test_migrations_up_status_down() {
+        let dir = env::temp_dir().join(format!("ujs-migrations-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        write_migration(
+            &dir,
+            "2025-01-01-000000_create_users",
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL)",
+            "DROP TABLE users",
+        );
+        write_migration(
+            &dir,
+            "2025-01-02-000000_add_email",
+            "ALTER TABLE users ADD COLUMN email TEXT",
+            "ALTER TABLE users DROP COLUMN email",
+        );
+        let dir_str = dir.to_str().unwrap();
+
+        let pool = build_pool("sqlite::memory:".to_string()).unwrap();
+        let mut conn = pool.get().unwrap();
+
+        let applied = run_pending(&mut conn, dir_str).unwrap();
+        assert_eq!(applied, vec!["20250101000000", "20250102000000"]);
+        assert!(run_pending(&mut conn, dir_str).unwrap().is_empty());
+        conn.execute("INSERT INTO users (name, email) VALUES ('a', 'a@example.com')").unwrap();
+
+        let reverted = revert(&mut conn, dir_str, 1).unwrap();
+        assert_eq!(reverted, vec!["20250102000000"]);
+
+        let statuses = status(&mut conn, dir_str).unwrap();
+        assert_eq!(statuses.len(), 2);
+        assert_eq!(statuses[0].name, "2025-01-01-000000_create_users");
+        assert!(statuses[0].applied);
+        assert!(!statuses[1].applied);
+
+        assert_eq!(revert(&mut conn, dir_str, 5).unwrap(), vec!["20250101000000"]);
+        assert!(conn.query("SELECT * FROM users").is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_shipped_migrations() {
+        let pool = build_pool("sqlite::memory:".to_string()).unwrap();
+        let mut conn = pool.get().unwrap();
+        assert!(!run_pending(&mut conn, "./migrations").unwrap().is_empty());
+        conn.execute("INSERT INTO js_users (name, email) VALUES ('a', 'a@example.com')").unwrap();
+        assert!(!revert(&mut conn, "./migrations", 100).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_missing_migrations_dir() {
+        let pool = build_pool("sqlite::memory:".to_string()).unwrap();
+        let mut conn = pool.get().unwrap();
+        assert!(run_pending(&mut conn, "./no-such-migrations").is_err());
+    }
+}
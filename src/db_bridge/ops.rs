@@ -1,9 +1,10 @@
+use super::{DbConnection, with_backend};
 use diesel::prelude::*;
 use diesel_dynamic_schema::table;
 use diesel::sql_types::Text;
 
 pub fn dynamic_insert(
-    conn: &mut PgConnection,
+    conn: &mut DbConnection,
     table_name: &str,
     columns: Vec<(&str, &str)>,
 ) -> QueryResult<usize> {
@@ -25,20 +26,22 @@ pub fn dynamic_insert(
     );
     
     // 动态多字段插入比较麻烦，这里演示 2 字段
-    if columns.len() == 2 {
-        diesel::sql_query(sql)
-            .bind::<Text, _>(columns[0].1)
-            .bind::<Text, _>(columns[1].1)
-            .execute(conn)
-    } else {
-        diesel::sql_query(sql)
-            .bind::<Text, _>(columns[0].1)
-            .execute(conn)
-    }
+    with_backend!(conn, DbConnection, conn => {
+        if columns.len() == 2 {
+            diesel::sql_query(sql)
+                .bind::<Text, _>(columns[0].1)
+                .bind::<Text, _>(columns[1].1)
+                .execute(conn)
+        } else {
+            diesel::sql_query(sql)
+                .bind::<Text, _>(columns[0].1)
+                .execute(conn)
+        }
+    })
 }
 
 pub fn dynamic_query(
-    conn: &mut PgConnection,
+    conn: &mut DbConnection,
     table_name: &str,
     target_columns: Vec<&str>,
 ) -> QueryResult<Vec<Vec<String>>> {
@@ -48,33 +51,41 @@ pub fn dynamic_query(
     if target_columns.len() == 2 {
         let col1 = table.column::<Text, _>(target_columns[0]);
         let col2 = table.column::<Text, _>(target_columns[1]);
-        let results: Vec<(String, String)> = table.select((col1, col2)).load(conn)?;
+        let results: Vec<(String, String)> =
+            with_backend!(conn, DbConnection, conn => table.select((col1, col2)).load(conn))?;
         Ok(results.into_iter().map(|(s1, s2)| vec![s1, s2]).collect())
     } else {
         let col1 = table.column::<Text, _>(target_columns[0]);
-        let results: Vec<String> = table.select(col1).load(conn)?;
+        let results: Vec<String> =
+            with_backend!(conn, DbConnection, conn => table.select(col1).load(conn))?;
         Ok(results.into_iter().map(|s| vec![s]).collect())
     }
 }
 
 pub fn dynamic_delete(
-    conn: &mut PgConnection,
+    conn: &mut DbConnection,
     table_name: &str,
     condition_col: &str,
     condition_val: &str,
 ) -> QueryResult<usize> {
     let sql = format!("DELETE FROM {} WHERE {} = $1", table_name, condition_col);
-    diesel::sql_query(sql)
-        .bind::<Text, _>(condition_val)
-        .execute(conn)
+    with_backend!(conn, DbConnection, conn => {
+        diesel::sql_query(sql)
+            .bind::<Text, _>(condition_val)
+            .execute(conn)
+    })
 }
 
-pub fn setup_test_table(conn: &mut PgConnection, table_name: &str) -> QueryResult<usize> {
-    let sql = format!("CREATE TABLE IF NOT EXISTS {} (id SERIAL PRIMARY KEY, name TEXT NOT NULL, email TEXT NOT NULL)", table_name);
-    diesel::sql_query(sql).execute(conn)
+pub fn setup_test_table(conn: &mut DbConnection, table_name: &str) -> QueryResult<usize> {
+    let id = match conn {
+        DbConnection::Postgres(_) => "id SERIAL PRIMARY KEY",
+        DbConnection::Sqlite(_) => "id INTEGER PRIMARY KEY AUTOINCREMENT",
+    };
+    let sql = format!("CREATE TABLE IF NOT EXISTS {} ({}, name TEXT NOT NULL, email TEXT NOT NULL)", table_name, id);
+    with_backend!(conn, DbConnection, conn => diesel::sql_query(sql).execute(conn))
 }
 
-pub fn drop_test_table(conn: &mut PgConnection, table_name: &str) -> QueryResult<usize> {
+pub fn drop_test_table(conn: &mut DbConnection, table_name: &str) -> QueryResult<usize> {
     let sql = format!("DROP TABLE IF EXISTS {}", table_name);
-    diesel::sql_query(sql).execute(conn)
+    with_backend!(conn, DbConnection, conn => diesel::sql_query(sql).execute(conn))
 }
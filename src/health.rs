@@ -0,0 +1,177 @@
+use crate::db_bridge::{self, DbPool, DbPools};
+use crate::js_bridge::executor::worker_pool::{WorkerPool, WorkerPoolStats};
+use crate::metrics::Metrics;
+use crate::websocket::WebSocketState;
+use axum::{
+    Json, Router,
+    extract::State,
+    http::StatusCode,
+    response::IntoResponse,
+    routing::get,
+};
+use serde::Serialize;
+use serde_json::json;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+/// 就绪检查获取数据库连接的超时时间
+const DB_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 脚本目录
+const SCRIPTS_DIR: &str = "./scripts";
+
+type AppState = (DbPool, Arc<WebSocketState>);
+
+/// 进程启动时间（首次调用时记录，`main` 启动时调用一次）
+pub fn started_at() -> Instant {
+    static STARTED: OnceLock<Instant> = OnceLock::new();
+    *STARTED.get_or_init(Instant::now)
+}
+
+/// 健康检查路由，不经过认证和限流，供负载均衡探测
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/status", get(status))
+}
+
+/// 单项检查结果
+#[derive(Debug, Serialize)]
+struct Check {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl Check {
+    fn from_result(result: Result<(), String>) -> Self {
+        match result {
+            Ok(()) => Self { ok: true, error: None },
+            Err(e) => Self {
+                ok: false,
+                error: Some(e),
+            },
+        }
+    }
+}
+
+/// GET /healthz - 进程存活即返回 200
+async fn healthz() -> impl IntoResponse {
+    Json(json!({ "status": "ok" }))
+}
+
+/// GET /readyz - 数据库、启动迁移、脚本目录和脚本线程池均可用时返回 200，否则 503
+async fn readyz(State((pool, _)): State<AppState>) -> impl IntoResponse {
+    let database = Check::from_result(check_database(pool).await);
+    let migrations = Check::from_result(db_bridge::migrations::startup_status());
+    let scripts = Check::from_result(check_scripts_dir(SCRIPTS_DIR));
+    let workers = Check::from_result(check_worker_pool(&WorkerPool::global().stats()));
+
+    let ready = database.ok && migrations.ok && scripts.ok && workers.ok;
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status,
+        Json(json!({
+            "status": if ready { "ready" } else { "not_ready" },
+            "checks": {
+                "database": database,
+                "migrations": migrations,
+                "scripts": scripts,
+                "workers": workers,
+            },
+        })),
+    )
+}
+
+/// GET /status - 版本、运行时间、连接池和脚本线程池状态
+async fn status(State((pool, _)): State<AppState>) -> impl IntoResponse {
+    let db = pool.state();
+    let db_status = db_bridge::status();
+    let workers = WorkerPool::global().stats();
+    let named_pools: serde_json::Map<String, serde_json::Value> = DbPools::global()
+        .iter()
+        .map(|(name, p)| {
+            let state = p.state();
+            (
+                name.to_string(),
+                json!({ "connections": state.connections, "idle": state.idle_connections }),
+            )
+        })
+        .collect();
+    Json(json!({
+        "name": env!("CARGO_PKG_NAME"),
+        "version": env!("CARGO_PKG_VERSION"),
+        "uptime_secs": started_at().elapsed().as_secs(),
+        "database": {
+            "available": db_status.available,
+            "last_error": db_status.last_error,
+            "max_size": pool.max_size(),
+            "connections": db.connections,
+            "idle": db.idle_connections,
+            "in_use": db.connections - db.idle_connections,
+        },
+        "named_pools": named_pools,
+        "workers": workers,
+        "active_scripts": workers.active,
+        "websocket_connections": Metrics::global().ws_connections.get(),
+    }))
+}
+
+/// 从连接池取一个连接并执行 `SELECT 1`
+async fn check_database(pool: DbPool) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        pool.get_timeout(DB_CHECK_TIMEOUT)
+            .and_then(|mut conn| conn.execute("SELECT 1"))
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("database check panicked: {}", e))?
+}
+
+fn check_scripts_dir(dir: &str) -> Result<(), String> {
+    std::fs::read_dir(dir)
+        .map(|_| ())
+        .map_err(|e| format!("{} is not readable: {}", dir, e))
+}
+
+/// 排队已满时新脚本会被拒绝，视为未就绪
+fn check_worker_pool(stats: &WorkerPoolStats) -> Result<(), String> {
+    if stats.queued >= stats.max_queue {
+        Err(format!(
+            "worker queue is full ({}/{})",
+            stats.queued, stats.max_queue
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_scripts_dir() {
+        assert!(check_scripts_dir(SCRIPTS_DIR).is_ok());
+        assert!(check_scripts_dir("./no-such-dir").is_err());
+    }
+
+    #[test]
+    fn test_check_worker_pool() {
+        let mut stats = WorkerPoolStats {
+            max_concurrent: 2,
+            max_queue: 4,
+            active: 2,
+            queued: 3,
+        };
+        assert!(check_worker_pool(&stats).is_ok());
+        stats.queued = 4;
+        assert!(check_worker_pool(&stats).is_err());
+    }
+}
@@ -1,10 +1,19 @@
 pub mod runtime_factory;
 pub mod script_runner;
+pub mod socket_executor;
+pub mod worker_pool;
 
 use crate::db_bridge::DbPool;
 use crate::js_bridge::executor::runtime_factory::RuntimeFactory;
-use crate::js_bridge::executor::script_runner::ScriptRunner;
+use crate::js_bridge::executor::script_runner::{ScriptRunner, ThrownRpcError};
+use crate::js_bridge::executor::worker_pool::WorkerPool;
 use crate::js_bridge::models::{JsRequest, JsResponse};
+use crate::js_bridge::ops::db_ops::DatabaseUnavailable;
+use crate::metrics::Metrics;
+use crate::telemetry;
+use crate::websocket::WebSocketState;
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::oneshot;
 
 /// 运行时配置
@@ -12,15 +21,28 @@ pub struct RuntimeConfig {
     pub script_path: String,
     pub request: JsRequest,
     pub db_pool: DbPool,
+    /// WebSocket 消息中心，脚本通过 `ws.publish` / `ws.broadcast` 推送消息
+    pub ws_state: Option<Arc<WebSocketState>>,
 }
 
 /// 脚本执行器 - 单一职责：协调整个脚本执行流程
 pub struct ScriptExecutor;
 
 impl ScriptExecutor {
-    /// 执行脚本
+    /// 执行脚本，超时设置取自 [`WorkerPool::global`]
     pub async fn execute(config: RuntimeConfig) -> JsResponse {
+        let pool = WorkerPool::global();
+        Self::execute_with_timeout(config, pool.script_timeout(), pool.script_linger()).await
+    }
+
+    /// 执行脚本
+    ///
+    /// `timeout` 限制发送响应前的执行时间（不含排队时间），超时返回 504；
+    /// 脚本发送响应后继续运行超过 `linger` 时同样终止，避免一直占用工作线程。
+    pub async fn execute_with_timeout(mut config: RuntimeConfig, timeout: Option<Duration>, linger: Duration) -> JsResponse {
         let (tx, rx) = oneshot::channel();
+        let (handle_tx, handle_rx) = oneshot::channel::<deno_core::v8::IsolateHandle>();
+        let (done_tx, done_rx) = oneshot::channel::<()>();
         let script_path = config.script_path.clone();
 
         // 检查脚本文件是否存在
@@ -28,10 +50,23 @@ impl ScriptExecutor {
             return JsResponse::not_found("Script not found");
         }
 
-        // 在独立线程中执行脚本
-        rayon::spawn(move || {
+        // 在脚本线程池中执行，队列已满时直接拒绝
+        let span = tracing::info_span!(
+            "script",
+            path = %script_path,
+            queue_wait_ms = tracing::field::Empty
+        );
+        // 脚本可以通过 request.traceparent 把追踪上下文继续传给下游服务
+        let traceparent = span.in_scope(telemetry::current_traceparent);
+        config.request = config.request.with_traceparent(traceparent);
+        let submitted = WorkerPool::global().try_spawn(move |queue_wait| {
+            span.record("queue_wait_ms", queue_wait.as_millis() as u64);
+            let _enter = span.enter();
+            tracing::debug!("script dequeued after {:?}", queue_wait);
+
             // 创建运行时
             let mut runtime = RuntimeFactory::create_runtime();
+            let _ = handle_tx.send(runtime.v8_isolate().thread_safe_handle());
 
             // 配置运行时
             RuntimeFactory::configure_runtime(
@@ -40,16 +75,75 @@ impl ScriptExecutor {
                 config.db_pool,
                 tx,
             );
+            if let Some(ws_state) = config.ws_state {
+                runtime.op_state().borrow_mut().put(ws_state);
+            }
 
             // 运行脚本
-            if let Err(e) = ScriptRunner::run_script(&mut runtime, &script_path) {
-                eprintln!("Script execution error: {}", e);
+            if let Err(e) = ScriptRunner::run_script(&mut runtime, &config.script_path) {
+                Metrics::global().script_errors.with_label_values(&["load"]).inc();
+                tracing::error!("Script execution error: {}", e);
+            }
+
+            // 脚本因取不到数据库连接而没有响应时，返回 503 让客户端稍后重试
+            let op_state = runtime.op_state();
+            let mut op_state = op_state.borrow_mut();
+            if let Some(DatabaseUnavailable(reason)) = op_state.try_take::<DatabaseUnavailable>()
+                && let Some(tx) = op_state.try_take::<oneshot::Sender<JsResponse>>()
+            {
+                tracing::warn!("script {} failed: database unavailable: {}", config.script_path, reason);
+                let _ = tx.send(JsResponse::service_unavailable("Database unavailable, please retry later"));
             }
+
+            // 脚本抛出 RpcError 而没有响应时，按脚本指定的错误码返回
+            if let Some(ThrownRpcError(error)) = op_state.try_take::<ThrownRpcError>()
+                && let Some(tx) = op_state.try_take::<oneshot::Sender<JsResponse>>()
+            {
+                let _ = tx.send(JsResponse::rpc_error(error));
+            }
+            drop(op_state);
+            drop(runtime);
+            let _ = done_tx.send(());
         });
+        if submitted.is_err() {
+            Metrics::global().script_errors.with_label_values(&["rejected"]).inc();
+            let stats = WorkerPool::global().stats();
+            tracing::warn!(?stats, "script worker pool is full, rejecting {}", script_path);
+            return JsResponse::service_unavailable("Server is busy, please retry later");
+        }
+
+        // 等待脚本开始执行（排队时间不计入超时）
+        let Ok(isolate) = handle_rx.await else {
+            return JsResponse::internal_error("Script worker terminated unexpectedly");
+        };
 
-        // 等待响应
-        rx.await.unwrap_or_else(|_| {
-            JsResponse::internal_error("JS failed to send response (did you forget to call Deno.core.ops.op_send_response?)")
-        })
+        // 等待响应，超时后终止脚本
+        let response = match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, rx).await,
+            None => Ok(rx.await),
+        };
+        match response {
+            Ok(Ok(response)) => {
+                // 响应已经发出，脚本仍在运行（定时器、未完成的 Promise 或死循环）时限制其剩余时间
+                tokio::spawn(async move {
+                    if tokio::time::timeout(linger, done_rx).await.is_err() {
+                        Metrics::global().script_timeouts.inc();
+                        tracing::warn!("script {} still running {:?} after responding, terminating", script_path, linger);
+                        isolate.terminate_execution();
+                    }
+                });
+                response
+            }
+            Ok(Err(_)) => {
+                Metrics::global().script_errors.with_label_values(&["no_response"]).inc();
+                JsResponse::internal_error("JS failed to send response (did you forget to call Deno.core.ops.op_send_response?)")
+            }
+            Err(_) => {
+                Metrics::global().script_timeouts.inc();
+                tracing::warn!("script {} timed out, terminating", script_path);
+                isolate.terminate_execution();
+                JsResponse::new(504, "Script execution timed out".to_string())
+            }
+        }
     }
 }
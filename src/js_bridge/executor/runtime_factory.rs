@@ -1,7 +1,10 @@
 use crate::db_bridge::DbPool;
 use crate::js_bridge::loader::TsModuleLoader;
 use crate::js_bridge::models::{JsRequest, JsResponse};
+use crate::js_bridge::ops::utility_ops::ScriptLogContext;
 use crate::js_bridge::ops::web_runtime;
+use crate::metrics::Metrics;
+use crate::session::{Session, SessionConfig};
 use deno_core::JsRuntime;
 use deno_core::RuntimeOptions;
 use std::rc::Rc;
@@ -13,11 +16,16 @@ pub struct RuntimeFactory;
 impl RuntimeFactory {
     /// 创建新的JavaScript运行时
     pub fn create_runtime() -> JsRuntime {
-        JsRuntime::new(RuntimeOptions {
+        let started = std::time::Instant::now();
+        let runtime = JsRuntime::new(RuntimeOptions {
             extensions: vec![web_runtime::init()],
             module_loader: Some(Rc::new(TsModuleLoader)),
             ..Default::default()
-        })
+        });
+        Metrics::global()
+            .isolate_create
+            .observe(started.elapsed().as_secs_f64());
+        runtime
     }
 
     /// 配置运行时状态
@@ -29,6 +37,17 @@ impl RuntimeFactory {
     ) -> u32 {
         // 设置响应通道
         runtime.op_state().borrow_mut().put(tx);
+        Self::configure_request(runtime, request, db_pool)
+    }
+
+    /// 注入请求、会话、日志上下文和数据库连接池（HTTP 脚本和 WebSocket 脚本共用）
+    pub fn configure_request(runtime: &mut JsRuntime, request: JsRequest, db_pool: DbPool) -> u32 {
+        // 从 Cookie 加载会话
+        let session = Session::load(SessionConfig::global(), request.headers.get("cookie").map(|s| s.as_str()));
+        runtime.op_state().borrow_mut().put(session);
+
+        // console / op_log 输出的日志字段
+        runtime.op_state().borrow_mut().put(ScriptLogContext::from_request(&request));
 
         // 添加请求资源
         let rid = runtime.op_state().borrow_mut().resource_table.add(request);
@@ -1,4 +1,13 @@
+use crate::js_bridge::models::JsonRpcError;
+use crate::metrics::Metrics;
 use deno_core::JsRuntime;
+use deno_core::error::{CoreError, CoreErrorKind};
+
+/// 脚本未捕获的 `RpcError`
+///
+/// 写入 OpState；脚本因此未发送响应时，执行器按其中的错误码返回 JSON-RPC 错误。
+#[derive(Debug, Clone)]
+pub struct ThrownRpcError(pub JsonRpcError);
 
 /// 脚本运行器 - 单一职责：加载和执行JavaScript脚本
 pub struct ScriptRunner;
@@ -17,13 +26,46 @@ impl ScriptRunner {
         })
     }
 
-    /// 异步运行脚本
-    async fn run_script_async(runtime: &mut JsRuntime, script_path: &str) -> Result<(), String> {
+    /// 运行 WebSocket 脚本
+    ///
+    /// 生成一个入口模块导入脚本的 `onOpen` / `onMessage` / `onClose`，交给 init.js 中的
+    /// `__serveSocket` 驱动，连接关闭、`onClose` 返回后结束。
+    pub fn run_socket_script(runtime: &mut JsRuntime, script_path: &str, socket_id: &str) -> Result<(), String> {
+        let tokio_runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| format!("Failed to create tokio runtime: {}", e))?;
+
+        tokio_runtime.block_on(async {
+            let specifier = Self::resolve(script_path)?;
+            let main_specifier = deno_core::ModuleSpecifier::parse("ujs:socket-main")
+                .map_err(|e| format!("Failed to resolve script path: {}", e))?;
+            let code = format!(
+                "import * as handlers from {};\nawait globalThis.__serveSocket(handlers, {});\n",
+                serde_json::Value::from(specifier.as_str()),
+                serde_json::Value::from(socket_id)
+            );
+
+            let mod_id = runtime
+                .load_main_es_module_from_code(&main_specifier, code)
+                .await
+                .map_err(|e| format!("Failed to load module: {}", e))?;
+            Self::evaluate(runtime, mod_id).await;
+            Ok(())
+        })
+    }
+
+    fn resolve(script_path: &str) -> Result<deno_core::ModuleSpecifier, String> {
         let cwd = std::env::current_dir()
             .map_err(|e| format!("Failed to get current directory: {}", e))?;
 
-        let specifier = deno_core::resolve_path(script_path, &cwd)
-            .map_err(|e| format!("Failed to resolve script path: {}", e))?;
+        deno_core::resolve_path(script_path, &cwd)
+            .map_err(|e| format!("Failed to resolve script path: {}", e))
+    }
+
+    /// 异步运行脚本
+    async fn run_script_async(runtime: &mut JsRuntime, script_path: &str) -> Result<(), String> {
+        let specifier = Self::resolve(script_path)?;
 
         // 加载主模块
         let mod_id = runtime
@@ -31,19 +73,46 @@ impl ScriptRunner {
             .await
             .map_err(|e| format!("Failed to load module: {}", e))?;
 
+        Self::evaluate(runtime, mod_id).await;
+        Ok(())
+    }
+
+    /// 执行模块并运行事件循环直到完成，脚本异常只记录不返回
+    async fn evaluate(runtime: &mut JsRuntime, mod_id: deno_core::ModuleId) {
         // 执行模块
         let evaluation = runtime.mod_evaluate(mod_id);
 
         // 运行事件循环直到模块执行完成
         if let Err(e) = runtime.run_event_loop(Default::default()).await {
-            eprintln!("Event loop error: {}", e);
+            Self::report(runtime, "Event loop error", &e);
         }
 
         // 检查评估结果
         if let Err(e) = evaluation.await {
-            eprintln!("Module evaluation error: {}", e);
+            Self::report(runtime, "Module evaluation error", &e);
         }
+    }
 
-        Ok(())
+    /// 记录脚本异常；`RpcError` 是脚本主动返回的错误，不计入异常指标
+    fn report(runtime: &mut JsRuntime, context: &str, e: &CoreError) {
+        if let Some(error) = Self::rpc_error(e) {
+            tracing::debug!("script threw RpcError {}: {}", error.code, error.message);
+            runtime.op_state().borrow_mut().put(ThrownRpcError(error));
+            return;
+        }
+        Metrics::global().script_errors.with_label_values(&["exception"]).inc();
+        tracing::error!("{}: {}", context, e);
+    }
+
+    /// 从 JS 异常中取出 init.js 中 `RpcError` 附带的错误对象
+    ///
+    /// `RpcError` 通过 `Symbol.for('errorAdditionalPropertyKeys')` 声明 `rpcError` 属性，
+    /// deno_core 会把它复制到 `JsError::additional_properties`。
+    fn rpc_error(e: &CoreError) -> Option<JsonRpcError> {
+        let CoreErrorKind::Js(js_error) = e.0.as_ref() else {
+            return None;
+        };
+        let (_, value) = js_error.additional_properties.iter().find(|(key, _)| key == "rpcError")?;
+        serde_json::from_str(value).ok()
     }
 }
@@ -0,0 +1,90 @@
+use crate::db_bridge::DbPool;
+use crate::js_bridge::executor::runtime_factory::RuntimeFactory;
+use crate::js_bridge::executor::script_runner::ScriptRunner;
+use crate::js_bridge::models::JsRequest;
+use crate::js_bridge::ops::socket_ops::{SocketChannel, SocketEvent};
+use crate::metrics::Metrics;
+use crate::websocket::WebSocketState;
+use axum::extract::ws::Message;
+use deno_core::v8::IsolateHandle;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Notify, mpsc, oneshot};
+
+/// WebSocket 脚本配置
+pub struct SocketConfig {
+    pub script_path: String,
+    /// 连接 ID，脚本中为 `socket.id`
+    pub socket_id: String,
+    /// 升级请求，脚本通过 `request` 读取请求头和认证身份
+    pub request: JsRequest,
+    pub db_pool: DbPool,
+    pub ws_state: Arc<WebSocketState>,
+    pub events: mpsc::Receiver<SocketEvent>,
+    pub outgoing: mpsc::Sender<Message>,
+    /// 发送队列写满时由 `socket.send` 通知
+    pub overflow: Arc<Notify>,
+}
+
+/// WebSocket 脚本执行器 - 单一职责：为每个连接运行一个独立的 isolate
+///
+/// 连接可能持续很久，不占用脚本线程池，也不受 `SCRIPT_TIMEOUT_MS` 限制；
+/// 每个连接一个线程，连接关闭且 `onClose` 返回后线程退出。
+pub struct SocketExecutor;
+
+impl SocketExecutor {
+    /// 启动连接的脚本线程
+    pub fn spawn(config: SocketConfig) -> Result<SocketWorker, String> {
+        let (handle_tx, handle_rx) = oneshot::channel();
+        let (done_tx, done_rx) = oneshot::channel();
+        let span = tracing::info_span!("socket", path = %config.script_path, socket.id = %config.socket_id);
+
+        std::thread::Builder::new()
+            .name(format!("ws-{}", config.socket_id))
+            .spawn(move || {
+                let _enter = span.enter();
+                let mut runtime = RuntimeFactory::create_runtime();
+                let _ = handle_tx.send(runtime.v8_isolate().thread_safe_handle());
+
+                RuntimeFactory::configure_request(&mut runtime, config.request, config.db_pool);
+                runtime.op_state().borrow_mut().put(config.ws_state);
+                runtime.op_state().borrow_mut().put(SocketChannel {
+                    events: Some(config.events),
+                    outgoing: config.outgoing,
+                    overflow: config.overflow,
+                });
+
+                if let Err(e) = ScriptRunner::run_socket_script(&mut runtime, &config.script_path, &config.socket_id) {
+                    Metrics::global().script_errors.with_label_values(&["load"]).inc();
+                    tracing::error!("socket script {} failed: {}", config.script_path, e);
+                }
+                let _ = done_tx.send(());
+            })
+            .map_err(|e| format!("Failed to spawn socket script thread: {}", e))?;
+
+        Ok(SocketWorker {
+            isolate: handle_rx,
+            done: done_rx,
+        })
+    }
+}
+
+/// 正在运行的连接脚本
+pub struct SocketWorker {
+    isolate: oneshot::Receiver<IsolateHandle>,
+    done: oneshot::Receiver<()>,
+}
+
+impl SocketWorker {
+    /// 连接关闭后等待脚本结束（`onClose` 返回），超过 `grace` 时终止 isolate
+    pub async fn finish(self, grace: Duration) {
+        if tokio::time::timeout(grace, self.done).await.is_ok() {
+            return;
+        }
+        if let Ok(isolate) = self.isolate.await {
+            Metrics::global().script_timeouts.inc();
+            tracing::warn!("socket script did not finish {:?} after close, terminating", grace);
+            isolate.terminate_execution();
+        }
+    }
+}
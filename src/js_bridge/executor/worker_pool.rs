@@ -0,0 +1,199 @@
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// 脚本线程池配置
+#[derive(Debug, Clone)]
+pub struct WorkerPoolConfig {
+    /// 同时执行的脚本数（工作线程数）
+    pub max_concurrent: usize,
+    /// 排队等待的最大脚本数，超出后直接拒绝
+    pub max_queue: usize,
+    /// 单个脚本的最长执行时间（不含排队时间），`None` 表示不限制
+    pub script_timeout: Option<Duration>,
+    /// 脚本发送响应后还能继续运行的时间，超时后终止以释放工作线程
+    pub script_linger: Duration,
+}
+
+impl Default for WorkerPoolConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent: std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(4),
+            max_queue: 256,
+            script_timeout: None,
+            script_linger: Duration::from_secs(5),
+        }
+    }
+}
+
+impl WorkerPoolConfig {
+    /// 从环境变量读取配置（`SCRIPT_MAX_CONCURRENT` / `SCRIPT_MAX_QUEUE` / `SCRIPT_TIMEOUT_MS` / `SCRIPT_LINGER_MS`）
+    pub fn from_env() -> Self {
+        dotenvy::dotenv().ok();
+        let defaults = Self::default();
+        let parse = |key: &str, default: usize| {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|v: &usize| *v > 0)
+                .unwrap_or(default)
+        };
+        Self {
+            max_concurrent: parse("SCRIPT_MAX_CONCURRENT", defaults.max_concurrent),
+            max_queue: parse("SCRIPT_MAX_QUEUE", defaults.max_queue),
+            script_timeout: std::env::var("SCRIPT_TIMEOUT_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|v: &u64| *v > 0)
+                .map(Duration::from_millis),
+            script_linger: Duration::from_millis(parse(
+                "SCRIPT_LINGER_MS",
+                defaults.script_linger.as_millis() as usize,
+            ) as u64),
+        }
+    }
+}
+
+/// 线程池已满
+#[derive(Debug)]
+pub struct PoolFull;
+
+/// 线程池运行状态
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub struct WorkerPoolStats {
+    pub max_concurrent: usize,
+    pub max_queue: usize,
+    /// 正在执行的脚本数
+    pub active: usize,
+    /// 排队中的脚本数
+    pub queued: usize,
+}
+
+/// 脚本专用线程池 - 单一职责：限制并发脚本数量并在过载时拒绝新任务
+///
+/// 使用独立的 rayon 线程池，不占用 rayon 全局线程池。
+pub struct WorkerPool {
+    config: WorkerPoolConfig,
+    pool: rayon::ThreadPool,
+    /// 已接收但未完成的任务数（执行中 + 排队中）
+    in_flight: AtomicUsize,
+    active: AtomicUsize,
+}
+
+impl WorkerPool {
+    pub fn new(config: WorkerPoolConfig) -> Self {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(config.max_concurrent)
+            .thread_name(|i| format!("js-worker-{}", i))
+            .build()
+            .expect("Failed to build script worker pool");
+        Self {
+            config,
+            pool,
+            in_flight: AtomicUsize::new(0),
+            active: AtomicUsize::new(0),
+        }
+    }
+
+    /// 全局线程池（首次访问时从环境变量初始化）
+    pub fn global() -> &'static WorkerPool {
+        static POOL: OnceLock<WorkerPool> = OnceLock::new();
+        POOL.get_or_init(|| Self::new(WorkerPoolConfig::from_env()))
+    }
+
+    /// 提交任务，任务参数为排队等待的时间；执行中与排队中的任务总数超过上限时返回 `PoolFull`
+    pub fn try_spawn<F>(&'static self, job: F) -> Result<(), PoolFull>
+    where
+        F: FnOnce(Duration) + Send + 'static,
+    {
+        let capacity = self.config.max_concurrent + self.config.max_queue;
+        self.in_flight
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+                (n < capacity).then_some(n + 1)
+            })
+            .map_err(|_| PoolFull)?;
+
+        let enqueued_at = Instant::now();
+        self.pool.spawn(move || {
+            let _guard = InFlightGuard(self);
+            self.active.fetch_add(1, Ordering::AcqRel);
+            job(enqueued_at.elapsed());
+        });
+        Ok(())
+    }
+
+    /// 单个脚本的最长执行时间
+    pub fn script_timeout(&self) -> Option<Duration> {
+        self.config.script_timeout
+    }
+
+    /// 脚本发送响应后还能继续运行的时间
+    pub fn script_linger(&self) -> Duration {
+        self.config.script_linger
+    }
+
+    pub fn stats(&self) -> WorkerPoolStats {
+        let in_flight = self.in_flight.load(Ordering::Acquire);
+        let active = self.active.load(Ordering::Acquire);
+        WorkerPoolStats {
+            max_concurrent: self.config.max_concurrent,
+            max_queue: self.config.max_queue,
+            active,
+            queued: in_flight.saturating_sub(active),
+        }
+    }
+}
+
+/// 任务结束（包括 panic）时释放计数
+struct InFlightGuard(&'static WorkerPool);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.0.active.fetch_sub(1, Ordering::AcqRel);
+        self.0.in_flight.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_worker_pool_sheds_load() {
+        let pool: &'static WorkerPool = Box::leak(Box::new(WorkerPool::new(WorkerPoolConfig {
+            max_concurrent: 1,
+            max_queue: 1,
+            script_timeout: None,
+            script_linger: Duration::from_secs(1),
+        })));
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let (started_tx, started_rx) = mpsc::channel::<()>();
+
+        // 占满执行槽位
+        pool.try_spawn(move |_| {
+            started_tx.send(()).unwrap();
+            release_rx.recv().unwrap();
+        })
+        .unwrap();
+        started_rx.recv().unwrap();
+
+        // 占满队列
+        let (done_tx, done_rx) = mpsc::channel::<Duration>();
+        pool.try_spawn(move |wait| done_tx.send(wait).unwrap()).unwrap();
+
+        let stats = pool.stats();
+        assert_eq!(stats.active, 1);
+        assert_eq!(stats.queued, 1);
+        assert!(pool.try_spawn(|_| {}).is_err());
+
+        release_tx.send(()).unwrap();
+        done_rx.recv().unwrap();
+        while pool.stats().active + pool.stats().queued > 0 {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert!(pool.try_spawn(|_| {}).is_ok());
+    }
+}
@@ -1,6 +1,8 @@
+use crate::auth::AuthIdentity;
 use crate::db_bridge::DbPool;
 use crate::js_bridge::executor::{RuntimeConfig, ScriptExecutor};
 use crate::js_bridge::models::{JsRequest, JsResponse};
+use crate::metrics::Metrics;
 use axum::{
     extract::{Path, Request, State},
     response::IntoResponse,
@@ -8,7 +10,7 @@ use axum::{
 use std::collections::HashMap;
 
 pub async fn handle_js_script(
-    State((pool, _)): State<(DbPool, std::sync::Arc<crate::websocket::WebSocketState>)>,
+    State((pool, ws_state)): State<(DbPool, std::sync::Arc<crate::websocket::WebSocketState>)>,
     Path(script_name): Path<String>,
     req: Request,
 ) -> impl IntoResponse {
@@ -30,14 +32,27 @@ pub async fn handle_js_script(
     };
     let body_str = String::from_utf8_lossy(&body_bytes).into_owned();
 
-    let js_req = JsRequest::new(method, path, headers, body_str);
+    let user = parts.extensions.get::<AuthIdentity>().cloned();
+    let js_req = JsRequest::new(method, path, headers, body_str).with_user(user);
+
+    let started = std::time::Instant::now();
+    let script_path = format!("./scripts/{}", script_name);
+    // 不存在的脚本统一归为一个标签，避免任意路径撑爆指标基数
+    let metric_path = if std::path::Path::new(&script_path).exists() {
+        script_name.as_str()
+    } else {
+        "<not_found>"
+    };
+    let metric_path = metric_path.to_string();
 
     let config = RuntimeConfig {
-        script_path: format!("./scripts/{}", script_name),
+        script_path,
         request: js_req,
         db_pool: pool,
+        ws_state: Some(ws_state),
     };
 
     let js_response: crate::js_bridge::models::JsResponse = ScriptExecutor::execute(config).await;
+    Metrics::global().observe_script(&metric_path, js_response.status, started.elapsed());
     js_response.into_response()
 }
@@ -1,5 +1,6 @@
 import {
     op_log,
+    op_console,
     op_send_response,
     op_delay,
     op_req_close,
@@ -8,10 +9,34 @@ import {
     op_req_headers,
     op_req_body,
     op_req_get_header,
+    op_req_user,
+    op_req_traceparent,
     op_sql_execute,
-    op_sql_query
+    op_sql_query,
+    op_sql_cursor_open,
+    op_sql_cursor_next,
+    op_sql_cursor_close,
+    op_session_get,
+    op_session_set,
+    op_session_remove,
+    op_session_all,
+    op_session_destroy,
+    op_ws_send,
+    op_ws_send_binary,
+    op_ws_close,
+    op_ws_next_event,
+    op_ws_publish,
+    op_ws_broadcast
 } from 'ext:core/ops';
 
+function decodeCookieValue(value) {
+    try {
+        return decodeURIComponent(value);
+    } catch {
+        return value;
+    }
+}
+
 export class Request {
     #rid;
 
@@ -39,6 +64,34 @@ export class Request {
         return op_req_get_header(this.#rid, k)
     }
 
+    get id() {
+        return op_req_get_header(this.#rid, 'x-request-id') ?? null;
+    }
+
+    get user() {
+        return op_req_user(this.#rid) ?? null;
+    }
+
+    get traceparent() {
+        return op_req_traceparent(this.#rid) ?? null;
+    }
+
+    cookies() {
+        const result = {};
+        const raw = op_req_get_header(this.#rid, 'cookie');
+        if (!raw) return result;
+        for (const pair of raw.split(';')) {
+            const idx = pair.indexOf('=');
+            if (idx < 0) continue;
+            result[pair.slice(0, idx).trim()] = decodeCookieValue(pair.slice(idx + 1).trim());
+        }
+        return result;
+    }
+
+    cookie(name) {
+        return this.cookies()[name];
+    }
+
     close() {
         op_req_close(this.#rid)
     }
@@ -51,7 +104,144 @@ Object.defineProperty(globalThis, 'request', {
     configurable: true
 });
 
+class RpcError extends Error {
+    constructor(code, message, data) {
+        super(message);
+        this.name = 'RpcError';
+        this.code = code;
+        this.data = data;
+    }
+
+    get [Symbol.for('errorAdditionalPropertyKeys')]() {
+        return ['rpcError'];
+    }
+
+    get rpcError() {
+        return JSON.stringify({ code: this.code, message: this.message, data: this.data });
+    }
+}
+
+globalThis.RpcError = RpcError;
+
+function cursor(name, sql, params = [], options = {}) {
+    const batchSize = options.batchSize ?? 100;
+    return {
+        async *[Symbol.asyncIterator]() {
+            const rid = op_sql_cursor_open(sql, params, name);
+            try {
+                while (true) {
+                    const rows = op_sql_cursor_next(rid, batchSize);
+                    if (rows.length === 0) {
+                        return;
+                    }
+                    yield* rows;
+                }
+            } finally {
+                op_sql_cursor_close(rid);
+            }
+        },
+    };
+}
+
+function database(name) {
+    return {
+        execute: (sql) => op_sql_execute(sql, name),
+        query: (sql) => op_sql_query(sql, name),
+        cursor: (sql, params, options) => cursor(name, sql, params, options),
+    };
+}
+
 globalThis.db = {
-    execute: (sql) => op_sql_execute(sql),
-    query: (sql) => op_sql_query(sql),
-};
\ No newline at end of file
+    ...database(""),
+    use: (name) => database(String(name).toLowerCase()),
+};
+
+globalThis.session = {
+    get: (key) => op_session_get(key) ?? undefined,
+    set: (key, value) => op_session_set(key, value),
+    remove: (key) => op_session_remove(key),
+    all: () => op_session_all(),
+    destroy: () => op_session_destroy(),
+};
+
+globalThis.ws = {
+    publish: (channel, message) => op_ws_publish(String(channel), message ?? null),
+    broadcast: (message) => op_ws_broadcast(message ?? null),
+};
+
+class Socket {
+    #id;
+
+    constructor(id) {
+        this.#id = id;
+    }
+
+    get id() {
+        return this.#id;
+    }
+
+    get headers() {
+        return globalThis.request.headers();
+    }
+
+    send(data) {
+        if (typeof data === 'string') {
+            op_ws_send(data);
+        } else if (data instanceof Uint8Array) {
+            op_ws_send_binary(data);
+        } else if (data instanceof ArrayBuffer) {
+            op_ws_send_binary(new Uint8Array(data));
+        } else {
+            op_ws_send(JSON.stringify(data));
+        }
+    }
+
+    close(code = 1000, reason = '') {
+        op_ws_close(code, reason);
+    }
+}
+
+async function callHandler(name, handler, ...args) {
+    if (typeof handler !== 'function') return;
+    try {
+        await handler(...args);
+    } catch (e) {
+        console.error(`${name} failed:`, e);
+    }
+}
+
+globalThis.__serveSocket = async (handlers, id) => {
+    const socket = new Socket(id);
+    await callHandler('onOpen', handlers.onOpen, socket);
+    while (true) {
+        const event = await op_ws_next_event();
+        if (event.type === 'close') {
+            await callHandler('onClose', handlers.onClose, socket, event.code, event.reason);
+            return;
+        }
+        await callHandler('onMessage', handlers.onMessage, socket, event.data);
+    }
+};
+
+function formatLogArg(arg) {
+    if (typeof arg === 'string') return arg;
+    if (arg instanceof Error) return arg.stack ?? `${arg.name}: ${arg.message}`;
+    try {
+        return JSON.stringify(arg) ?? String(arg);
+    } catch {
+        return String(arg);
+    }
+}
+
+function consoleMethod(level) {
+    return (...args) => op_console(level, args.map(formatLogArg).join(' '));
+}
+
+Object.assign(globalThis.console, {
+    log: consoleMethod('info'),
+    info: consoleMethod('info'),
+    debug: consoleMethod('debug'),
+    trace: consoleMethod('debug'),
+    warn: consoleMethod('warn'),
+    error: consoleMethod('error'),
+});
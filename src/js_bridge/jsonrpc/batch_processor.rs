@@ -1,57 +1,105 @@
-use crate::db_bridge::DbPool;
 use crate::js_bridge::executor::{RuntimeConfig, ScriptExecutor};
+use crate::js_bridge::jsonrpc::context::RpcContext;
+use crate::js_bridge::jsonrpc::method_registry::MethodRegistry;
+use crate::js_bridge::jsonrpc::param_schema::SchemaCache;
+use crate::js_bridge::jsonrpc::request_parser::RequestError;
 use crate::js_bridge::jsonrpc::request_validator::RequestValidator;
 use crate::js_bridge::models::{JsonRpcError, JsonRpcRequest, JsonRpcResponse};
-use std::collections::HashMap;
+use crate::metrics::Metrics;
+use tracing::Instrument;
 
 /// 批量请求处理器 - 单一职责：处理批量JSON-RPC请求
 pub struct BatchProcessor;
 
 impl BatchProcessor {
     /// 处理批量请求
+    ///
+    /// 不合法的元素各自回复解析时的错误；通知不产生响应，全部是通知时返回空列表。
     pub async fn process_batch(
-        requests: Vec<JsonRpcRequest>,
-        pool: DbPool,
-        headers: HashMap<String, String>,
+        requests: Vec<Result<JsonRpcRequest, RequestError>>,
+        ctx: RpcContext,
     ) -> Vec<JsonRpcResponse> {
         // 并行处理所有请求
-        let futures = requests
-            .into_iter()
-            .map(|req| Self::process_single(req, pool.clone(), headers.clone()));
+        let futures = requests.into_iter().map(|req| {
+            let ctx = ctx.clone();
+            async move {
+                match req {
+                    Ok(req) => Self::process_single(req, ctx).await,
+                    Err(err) => Some(err.into_response().with_request_id(ctx.request_id.as_deref())),
+                }
+            }
+        });
 
-        futures::future::join_all(futures).await
+        futures::future::join_all(futures).await.into_iter().flatten().collect()
     }
 
-    /// 处理单个请求（供批量处理使用）
+    /// 处理单个请求（供批量处理使用），通知执行后返回 `None`
     pub async fn process_single(
         json_req: JsonRpcRequest,
-        pool: DbPool,
-        headers: HashMap<String, String>,
-    ) -> JsonRpcResponse {
+        ctx: RpcContext,
+    ) -> Option<JsonRpcResponse> {
+        let started = std::time::Instant::now();
+        let notification = RequestValidator::is_notification(&json_req);
+        // 不存在的方法统一归为一个标签，避免任意方法名撑爆指标基数
+        let metric_method = if MethodRegistry::global().resolve(&json_req.method).is_ok() {
+            json_req.method.clone()
+        } else {
+            "<unknown>".to_string()
+        };
+
+        let span = tracing::info_span!(
+            "rpc",
+            otel.name = %format!("rpc {}", json_req.method),
+            rpc.system = "jsonrpc",
+            rpc.method = %json_req.method,
+        );
+        let http_request_id = ctx.request_id.clone();
+        let response = Self::dispatch(json_req, ctx)
+            .instrument(span)
+            .await
+            .with_request_id(http_request_id.as_deref());
+        Metrics::global().observe_rpc(&metric_method, response.error.is_none(), started.elapsed());
+        (!notification).then_some(response)
+    }
+
+    /// 验证并分发单个请求
+    async fn dispatch(json_req: JsonRpcRequest, ctx: RpcContext) -> JsonRpcResponse {
         let request_id = json_req.id.clone();
 
-        // 验证请求
+        // 验证请求；不合法的请求不是通知，缺少 id 时按规范回复 `"id": null`
         if let Err(err) = RequestValidator::validate_request(&json_req) {
+            return JsonRpcResponse::error(err, Some(request_id.unwrap_or(serde_json::Value::Null)));
+        }
+
+        // 查找方法对应的脚本
+        let script_path = match MethodRegistry::global().resolve(&json_req.method) {
+            Ok(path) => path,
+            Err(err) => return JsonRpcResponse::error(err, request_id),
+        };
+
+        // 验证调用权限
+        if let Some(acl) = &ctx.acl
+            && let Err(err) = acl.check(&json_req.method, ctx.user.as_ref())
+        {
             return JsonRpcResponse::error(err, request_id);
         }
 
-        // 验证脚本存在
-        if let Err(err) = RequestValidator::validate_script_exists(&json_req.method) {
+        // 按参数 schema 校验，不通过时不启动 isolate
+        if let Err(err) = SchemaCache::global().validate(&script_path, json_req.params.as_ref()) {
             return JsonRpcResponse::error(err, request_id);
         }
 
         // 执行脚本
-        Self::execute_script(json_req, pool, headers).await
+        Self::execute_script(json_req, script_path.to_string_lossy().into_owned(), ctx).await
     }
 
     /// 执行脚本
     async fn execute_script(
         json_req: JsonRpcRequest,
-        pool: DbPool,
-        headers: HashMap<String, String>,
+        script_path: String,
+        ctx: RpcContext,
     ) -> JsonRpcResponse {
         let request_id = json_req.id.clone();
-        let script_path = format!("./scripts/{}.js", json_req.method);
 
         let params_json = json_req.params.unwrap_or(serde_json::Value::Null);
         let body_str = params_json.to_string();
@@ -59,14 +107,16 @@ impl BatchProcessor {
         let js_req = crate::js_bridge::models::JsRequest::new(
             "JSON-RPC".to_string(),
             format!("/rpc/{}", json_req.method),
-            headers,
+            ctx.headers,
             body_str,
-        );
+        )
+        .with_user(ctx.user);
 
         let config = RuntimeConfig {
             script_path,
             request: js_req,
-            db_pool: pool,
+            db_pool: ctx.pool,
+            ws_state: ctx.ws_state,
         };
 
         let js_response = ScriptExecutor::execute(config).await;
@@ -79,7 +129,9 @@ impl BatchProcessor {
         js_response: crate::js_bridge::models::JsResponse,
         request_id: Option<serde_json::Value>,
     ) -> JsonRpcResponse {
-        if js_response.status == 200 {
+        let response = if let Some(err) = js_response.rpc_error {
+            JsonRpcResponse::error(err, request_id)
+        } else if js_response.status == 200 {
             let result: serde_json::Value = match serde_json::from_str(&js_response.body) {
                 Ok(v) => v,
                 Err(_) => serde_json::json!(js_response.body),
@@ -87,6 +139,7 @@ impl BatchProcessor {
             JsonRpcResponse::success(result, request_id)
         } else {
             JsonRpcResponse::error(JsonRpcError::internal_error(&js_response.body), request_id)
-        }
+        };
+        response.with_logs(js_response.logs)
     }
 }
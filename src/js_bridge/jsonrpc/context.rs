@@ -0,0 +1,67 @@
+use crate::auth::{AuthIdentity, RpcAcl};
+use crate::db_bridge::DbPool;
+use crate::rate_limit::RateLimitClient;
+use crate::websocket::WebSocketState;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// JSON-RPC 调用上下文 - 单一职责：携带一次 HTTP 请求中所有调用共享的数据
+#[derive(Clone)]
+pub struct RpcContext {
+    pub pool: DbPool,
+    pub headers: HashMap<String, String>,
+    /// 认证中间件写入的身份
+    pub user: Option<AuthIdentity>,
+    /// 认证中间件写入的方法访问控制列表
+    pub acl: Option<Arc<RpcAcl>>,
+    /// HTTP 请求 ID（`X-Request-Id`），写入错误响应的 data
+    pub request_id: Option<String>,
+    /// WebSocket 消息中心，脚本通过 `ws.publish` / `ws.broadcast` 推送消息
+    pub ws_state: Option<Arc<WebSocketState>>,
+    /// WebSocket 连接的限流器，每次调用消耗一个令牌（`/rpc` 由限流中间件计数，为 `None`）
+    pub rate_limit: Option<RateLimitClient>,
+}
+
+impl RpcContext {
+    pub fn new(pool: DbPool, headers: HashMap<String, String>) -> Self {
+        Self {
+            pool,
+            headers,
+            user: None,
+            acl: None,
+            request_id: None,
+            ws_state: None,
+            rate_limit: None,
+        }
+    }
+
+    /// 设置已认证身份
+    pub fn with_user(mut self, user: Option<AuthIdentity>) -> Self {
+        self.user = user;
+        self
+    }
+
+    /// 设置 HTTP 请求 ID
+    pub fn with_request_id(mut self, request_id: Option<String>) -> Self {
+        self.request_id = request_id;
+        self
+    }
+
+    /// 设置 WebSocket 消息中心
+    pub fn with_ws_state(mut self, ws_state: Arc<WebSocketState>) -> Self {
+        self.ws_state = Some(ws_state);
+        self
+    }
+
+    /// 设置访问控制列表
+    pub fn with_acl(mut self, acl: Option<Arc<RpcAcl>>) -> Self {
+        self.acl = acl;
+        self
+    }
+
+    /// 设置限流器
+    pub fn with_rate_limit(mut self, rate_limit: Option<RateLimitClient>) -> Self {
+        self.rate_limit = rate_limit;
+        self
+    }
+}
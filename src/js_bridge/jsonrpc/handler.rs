@@ -1,83 +1,82 @@
 use crate::db_bridge::DbPool;
 use crate::js_bridge::jsonrpc::batch_processor::BatchProcessor;
-use crate::js_bridge::jsonrpc::request_parser::{JsonRpcRequestType, RequestParser};
+use crate::js_bridge::jsonrpc::context::RpcContext;
+use crate::js_bridge::jsonrpc::request_parser::{JsonRpcRequestType, RequestError, RequestParser};
 use crate::js_bridge::jsonrpc::request_validator::RequestValidator;
 use crate::js_bridge::jsonrpc::response_builder::ResponseBuilder;
+use crate::js_bridge::models::JsonRpcRequest;
+use crate::request_id::RequestId;
 use axum::extract::{Request, State};
 use axum::response::IntoResponse;
 
 /// JSON-RPC处理器 - 单一职责：协调请求解析、验证、处理和响应构建
 pub async fn handle_json_rpc(
-    State((pool, _)): State<(DbPool, std::sync::Arc<crate::websocket::WebSocketState>)>,
+    State((pool, ws_state)): State<(DbPool, std::sync::Arc<crate::websocket::WebSocketState>)>,
     req: Request,
 ) -> impl IntoResponse {
+    let request_id = req.extensions().get::<RequestId>().map(|id| id.0.clone());
+
     // 解析HTTP请求
     let parsed_req = match RequestParser::parse_http_request(req).await {
         Ok(req) => req,
-        Err(err) => return ResponseBuilder::build_error_response(err),
+        Err(err) => {
+            return ResponseBuilder::build_error_response(err.with_request_id(request_id.as_deref()));
+        }
     };
 
     // 解析JSON-RPC请求
     let json_rpc_req = match RequestParser::parse_json_rpc_request(&parsed_req.body) {
         Ok(req) => req,
-        Err(err) => return ResponseBuilder::build_error_response(err),
+        Err(err) => {
+            return ResponseBuilder::build_response(err.into_response().with_request_id(request_id.as_deref()));
+        }
     };
 
+    let ctx = RpcContext::new(pool, parsed_req.headers)
+        .with_user(parsed_req.user)
+        .with_acl(parsed_req.acl)
+        .with_request_id(parsed_req.request_id)
+        .with_ws_state(ws_state);
+
     // 根据请求类型处理
     match json_rpc_req {
         JsonRpcRequestType::Single(req) => {
-            handle_single_request(req, pool, parsed_req.headers)
+            handle_single_request(req, ctx)
                 .await
                 .into_response()
         }
         JsonRpcRequestType::Batch(reqs) => {
-            handle_batch_request(reqs, pool, parsed_req.headers)
+            handle_batch_request(reqs, ctx)
                 .await
                 .into_response()
         }
     }
 }
 
-/// 处理单个请求
+/// 处理单个请求，通知执行后返回 204
 async fn handle_single_request(
-    req: crate::js_bridge::models::JsonRpcRequest,
-    pool: DbPool,
-    headers: std::collections::HashMap<String, String>,
+    req: JsonRpcRequest,
+    ctx: RpcContext,
 ) -> impl IntoResponse {
-    // 验证请求
-    if let Err(err) = RequestValidator::validate_request(&req) {
-        return ResponseBuilder::build_response(crate::js_bridge::models::JsonRpcResponse::error(
-            err,
-            req.id,
-        ));
-    }
-
-    // 验证脚本存在
-    if let Err(err) = RequestValidator::validate_script_exists(&req.method) {
-        return ResponseBuilder::build_response(crate::js_bridge::models::JsonRpcResponse::error(
-            err,
-            req.id,
-        ));
+    // 验证和方法查找在 BatchProcessor 中进行，通知出错时同样不回复
+    match BatchProcessor::process_single(req, ctx).await {
+        Some(response) => ResponseBuilder::build_response(response),
+        None => ResponseBuilder::build_empty_response(),
     }
-
-    // 处理请求
-    let response = BatchProcessor::process_single(req, pool, headers).await;
-    ResponseBuilder::build_response(response)
 }
 
 /// 处理批量请求
 async fn handle_batch_request(
-    reqs: Vec<crate::js_bridge::models::JsonRpcRequest>,
-    pool: DbPool,
-    headers: std::collections::HashMap<String, String>,
+    reqs: Vec<Result<JsonRpcRequest, RequestError>>,
+    ctx: RpcContext,
 ) -> impl IntoResponse {
     // 验证批量请求不为空
     if let Err(err) = RequestValidator::validate_batch_not_empty(&reqs) {
-        return ResponseBuilder::build_error_response(err);
+        return ResponseBuilder::build_error_response(err.with_request_id(ctx.request_id.as_deref()));
     }
 
     // 处理批量请求
-    let responses = BatchProcessor::process_batch(reqs, pool, headers).await;
+    let responses = BatchProcessor::process_batch(reqs, ctx).await;
     ResponseBuilder::build_batch_response(responses)
 }
 
@@ -160,4 +159,35 @@ mod tests {
 
         assert_eq!(response.status(), axum::http::StatusCode::OK);
     }
+
+    #[tokio::test]
+    async fn test_invalid_request_without_id_gets_null_id() {
+        let pool = crate::db_bridge::get_test_pool();
+        let ws_state = crate::websocket::create_websocket_state();
+
+        // 不合法的请求不是通知，缺少 id 时回复 "id": null
+        let request = create_test_request(json!({ "jsonrpc": "1.0", "method": "add" }));
+        let response = handle_json_rpc(State((pool.clone(), ws_state.clone())), request)
+            .await
+            .into_response();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), 4096).await.unwrap();
+        let parsed: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(parsed["error"]["code"], -32600);
+        assert_eq!(parsed.get("id"), Some(&json!(null)));
+
+        let request = create_test_request(json!([
+            { "jsonrpc": "1.0", "method": "add" },
+            { "jsonrpc": "2.0", "method": "" }
+        ]));
+        let response = handle_json_rpc(State((pool.clone(), ws_state)), request)
+            .await
+            .into_response();
+        let body = axum::body::to_bytes(response.into_body(), 4096).await.unwrap();
+        let parsed: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let responses = parsed.as_array().unwrap();
+        assert_eq!(responses.len(), 2);
+        assert!(responses.iter().all(|r| r.get("id") == Some(&json!(null)) && r["error"]["code"] == -32600));
+    }
+
 }
\ No newline at end of file
@@ -0,0 +1,213 @@
+use crate::js_bridge::models::JsonRpcError;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// 方法名最大长度
+const MAX_METHOD_LEN: usize = 128;
+
+/// 同名脚本同时存在时优先使用 `.ts`
+const SCRIPT_EXTENSIONS: [&str; 2] = ["ts", "js"];
+
+/// 带命名空间的方法所在的子目录
+const RPC_DIR: &str = "rpc";
+
+/// JSON-RPC 方法注册表 - 单一职责：把方法名映射到脚本文件
+///
+/// `user.get` 对应 `scripts/rpc/user/get.ts`（或 `.js`）。显式配置允许列表时，不带 `.` 的方法
+/// 也可以直接放在 `scripts/` 下（如 `hello` 对应 `scripts/hello.js`），兼容原来的映射方式。
+/// 只有匹配允许列表的方法才对外暴露，见 [`MethodRegistry::from_env`]。
+#[derive(Debug, Clone)]
+pub struct MethodRegistry {
+    root: PathBuf,
+    /// 允许的方法：精确名称、`user.*` 命名空间前缀或 `*`
+    allow: Vec<String>,
+    /// 是否查找 `scripts/` 下的顶层脚本
+    top_level: bool,
+}
+
+impl MethodRegistry {
+    /// 按允许列表暴露方法，包括 `scripts/` 下的顶层脚本
+    pub fn new(root: impl Into<PathBuf>, allow: Vec<String>) -> Self {
+        Self {
+            root: root.into(),
+            allow,
+            top_level: true,
+        }
+    }
+
+    /// 只暴露 `scripts/rpc/` 下的脚本
+    pub fn rpc_only(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            allow: vec!["*".to_string()],
+            top_level: false,
+        }
+    }
+
+    /// 从环境变量读取允许列表
+    ///
+    /// `RPC_METHODS` 为逗号分隔的方法或命名空间（如 `add,user.*`）；未设置时只暴露 `scripts/rpc/` 下的脚本，
+    /// `scripts/` 下的其他脚本（HTTP 脚本、WebSocket 脚本、模块）不会被当作方法调用。
+    pub fn from_env() -> Self {
+        dotenvy::dotenv().ok();
+        let Ok(value) = std::env::var("RPC_METHODS") else {
+            return Self::rpc_only("./scripts");
+        };
+        let allow: Vec<String> = value
+            .split(',')
+            .map(str::trim)
+            .filter(|pattern| !pattern.is_empty())
+            .map(str::to_string)
+            .collect();
+        if allow.iter().any(|pattern| pattern == "*") {
+            tracing::warn!("RPC_METHODS=* exposes every script under ./scripts as a JSON-RPC method");
+        }
+        Self::new("./scripts", allow)
+    }
+
+    /// 全局注册表（单例模式）
+    pub fn global() -> &'static MethodRegistry {
+        static REGISTRY: OnceLock<MethodRegistry> = OnceLock::new();
+        REGISTRY.get_or_init(Self::from_env)
+    }
+
+    /// 验证方法名：由 `.` 分隔的若干段，每段只能包含字母、数字、`_` 和 `-`，且不以 `-` 开头
+    pub fn validate_name(method: &str) -> Result<(), JsonRpcError> {
+        let valid_segment = |segment: &str| {
+            !segment.is_empty()
+                && !segment.starts_with('-')
+                && segment.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        };
+        if method.len() > MAX_METHOD_LEN || !method.split('.').all(valid_segment) {
+            return Err(JsonRpcError::invalid_request(&format!("invalid method name \"{}\"", method)));
+        }
+        Ok(())
+    }
+
+    /// 方法是否在允许列表中
+    pub fn is_allowed(&self, method: &str) -> bool {
+        self.allow.iter().any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => method.starts_with(prefix),
+            None => pattern == method,
+        })
+    }
+
+    /// 解析方法对应的脚本路径
+    ///
+    /// 方法名非法时返回 `Invalid Request`，未暴露或脚本不存在时返回 `Method not found`。
+    pub fn resolve(&self, method: &str) -> Result<PathBuf, JsonRpcError> {
+        Self::validate_name(method)?;
+        if !self.is_allowed(method) {
+            return Err(JsonRpcError::method_not_found(method));
+        }
+        let segments: Vec<&str> = method.split('.').collect();
+        let mut candidates = vec![self.root.join(RPC_DIR).join(segments.join("/"))];
+        if segments.len() == 1 && self.top_level {
+            candidates.push(self.root.join(method));
+        }
+        candidates
+            .iter()
+            .flat_map(|base| SCRIPT_EXTENSIONS.iter().map(move |ext| base.with_extension(ext)))
+            .find(|path| path.is_file())
+            .ok_or_else(|| JsonRpcError::method_not_found(method))
+    }
+
+    /// 列出当前暴露的所有方法，按名称排序
+    pub fn methods(&self) -> Vec<String> {
+        let mut methods = Vec::new();
+        collect_methods(&self.root.join(RPC_DIR), &mut Vec::new(), &mut methods);
+        if self.top_level
+            && let Ok(entries) = std::fs::read_dir(&self.root)
+        {
+            methods.extend(entries.flatten().filter_map(|entry| script_method(&entry.path())));
+        }
+        methods.retain(|method| Self::validate_name(method).is_ok() && self.is_allowed(method));
+        methods.sort();
+        methods.dedup();
+        methods
+    }
+}
+
+/// 脚本文件对应的方法名（不含扩展名），不是脚本时返回 `None`
+fn script_method(path: &Path) -> Option<String> {
+    let ext = path.extension()?.to_str()?;
+    if !path.is_file() || !SCRIPT_EXTENSIONS.contains(&ext) {
+        return None;
+    }
+    Some(path.file_stem()?.to_str()?.to_string())
+}
+
+fn collect_methods(dir: &Path, prefix: &mut Vec<String>, methods: &mut Vec<String>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.is_dir() {
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            prefix.push(name.to_string());
+            collect_methods(&path, prefix, methods);
+            prefix.pop();
+        } else if let Some(name) = script_method(&path) {
+            methods.push(prefix.iter().map(String::as_str).chain([name.as_str()]).collect::<Vec<_>>().join("."));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry(allow: &[&str]) -> MethodRegistry {
+        MethodRegistry::new("./scripts", allow.iter().map(|s| s.to_string()).collect())
+    }
+
+    #[test]
+    fn test_validate_name() {
+        for method in ["add", "user.get", "user_admin.list-all", "v2.user.get"] {
+            assert!(MethodRegistry::validate_name(method).is_ok(), "{}", method);
+        }
+        for method in ["", "../x", "a/b", "a\\b", ".a", "a.", "a..b", "-a", "a b", "用户"] {
+            let err = MethodRegistry::validate_name(method).unwrap_err();
+            assert_eq!(err.code, -32600, "{}", method);
+        }
+        assert!(MethodRegistry::validate_name(&"a".repeat(129)).is_err());
+    }
+
+    #[test]
+    fn test_resolve() {
+        let registry = registry(&["*"]);
+        assert_eq!(registry.resolve("add").unwrap(), Path::new("./scripts/rpc/add.js"));
+        assert_eq!(registry.resolve("math.sum").unwrap(), Path::new("./scripts/rpc/math/sum.ts"));
+        assert_eq!(registry.resolve("lib").unwrap(), Path::new("./scripts/lib.ts"));
+        assert_eq!(registry.resolve("nonexistent").unwrap_err().code, -32601);
+        assert_eq!(registry.resolve("../scripts/add").unwrap_err().code, -32600);
+        // 带命名空间的方法不会回退到 scripts/ 下的同名文件
+        assert_eq!(registry.resolve("rpc.math.sum").unwrap_err().code, -32601);
+    }
+
+    #[test]
+    fn test_rpc_only() {
+        let registry = MethodRegistry::rpc_only("./scripts");
+        assert!(registry.resolve("add").is_ok());
+        assert!(registry.resolve("math.sum").is_ok());
+        for method in ["lib", "db_test", "pg_notify", "ws_chat"] {
+            assert_eq!(registry.resolve(method).unwrap_err().code, -32601, "{}", method);
+        }
+        assert_eq!(registry.methods(), vec!["add", "math.divide", "math.sum", "multiply"]);
+    }
+
+    #[test]
+    fn test_allow_list() {
+        let registry = registry(&["add", "math.*"]);
+        assert!(registry.resolve("add").is_ok());
+        assert!(registry.resolve("math.sum").is_ok());
+        assert_eq!(registry.resolve("multiply").unwrap_err().code, -32601);
+        assert!(!registry.is_allowed("mathematics"));
+
+        let methods = registry.methods();
+        assert_eq!(methods, vec!["add".to_string(), "math.divide".to_string(), "math.sum".to_string()]);
+        assert!(self::registry(&["*"]).methods().contains(&"multiply".to_string()));
+    }
+}
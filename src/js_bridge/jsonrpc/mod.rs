@@ -1,5 +1,8 @@
 pub mod batch_processor;
+pub mod context;
 pub mod handler;
+pub mod method_registry;
+pub mod param_schema;
 pub mod request_parser;
 pub mod request_validator;
 pub mod response_builder;
@@ -0,0 +1,307 @@
+use crate::js_bridge::models::JsonRpcError;
+use deno_ast::swc::ast::{Decl, Expr, Lit, ModuleDecl, ModuleItem, Pat, Prop, PropName, PropOrSpread, UnaryOp};
+use deno_ast::{MediaType, ParseParams, ProgramRef};
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+
+/// 一条参数校验错误
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Violation {
+    /// 出错位置的 JSON Pointer，参数本身为 `""`
+    pub path: String,
+    pub message: String,
+}
+
+/// 方法参数的 JSON Schema，由 `jsonschema` 编译和校验
+///
+/// 按 `$schema` 自动选择草案版本（默认 2020-12）；不解析外部 `$ref`（http / 文件）。
+#[derive(Debug, Clone)]
+pub struct ParamSchema(jsonschema::Validator);
+
+impl ParamSchema {
+    /// 编译 schema，schema 本身不合法时返回错误
+    pub fn compile(schema: Value) -> Result<Self, String> {
+        jsonschema::validator_for(&schema).map(Self).map_err(|e| e.to_string())
+    }
+
+    /// 校验参数，返回所有错误
+    pub fn validate(&self, params: &Value) -> Vec<Violation> {
+        self.0
+            .iter_errors(params)
+            .map(|error| Violation {
+                path: error.instance_path().as_str().to_string(),
+                message: error.to_string(),
+            })
+            .collect()
+    }
+}
+
+/// 把 `export const schema = { ... }` 的字面量转换为 JSON
+fn literal_to_json(expr: &Expr) -> Result<Value, String> {
+    match expr {
+        Expr::Lit(Lit::Str(s)) => Ok(json!(s.value.to_string_lossy())),
+        Expr::Lit(Lit::Num(n)) => Ok(number_to_json(n.value)),
+        Expr::Lit(Lit::Bool(b)) => Ok(json!(b.value)),
+        Expr::Lit(Lit::Null(_)) => Ok(Value::Null),
+        Expr::Unary(unary) if unary.op == UnaryOp::Minus => match unary.arg.as_ref() {
+            Expr::Lit(Lit::Num(n)) => Ok(number_to_json(-n.value)),
+            _ => Err("only numeric literals can be negated".to_string()),
+        },
+        Expr::Tpl(tpl) if tpl.exprs.is_empty() && tpl.quasis.len() == 1 => tpl.quasis[0]
+            .cooked
+            .as_ref()
+            .map(|s| json!(s.to_string_lossy()))
+            .ok_or_else(|| "invalid template literal".to_string()),
+        Expr::Paren(paren) => literal_to_json(&paren.expr),
+        Expr::TsAs(e) => literal_to_json(&e.expr),
+        Expr::TsConstAssertion(e) => literal_to_json(&e.expr),
+        Expr::TsSatisfies(e) => literal_to_json(&e.expr),
+        Expr::Array(array) => array
+            .elems
+            .iter()
+            .map(|elem| match elem {
+                Some(elem) if elem.spread.is_none() => literal_to_json(&elem.expr),
+                _ => Err("array holes and spread elements are not supported".to_string()),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array),
+        Expr::Object(object) => {
+            let mut map = Map::new();
+            for prop in &object.props {
+                let PropOrSpread::Prop(prop) = prop else {
+                    return Err("spread properties are not supported".to_string());
+                };
+                let Prop::KeyValue(kv) = prop.as_ref() else {
+                    return Err("only `key: value` properties are supported".to_string());
+                };
+                let key = match &kv.key {
+                    PropName::Ident(ident) => ident.sym.to_string(),
+                    PropName::Str(s) => s.value.to_string_lossy().into_owned(),
+                    PropName::Num(n) => n.value.to_string(),
+                    _ => return Err("computed property names are not supported".to_string()),
+                };
+                map.insert(key, literal_to_json(&kv.value)?);
+            }
+            Ok(Value::Object(map))
+        }
+        _ => Err("schema must be a JSON-compatible literal".to_string()),
+    }
+}
+
+fn number_to_json(n: f64) -> Value {
+    if n.fract() == 0.0 && n.abs() < i64::MAX as f64 {
+        json!(n as i64)
+    } else {
+        json!(n)
+    }
+}
+
+/// 读取脚本导出的 `schema`，没有导出时返回 `None`
+fn exported_schema(script_path: &Path) -> Result<Option<Value>, String> {
+    let code = std::fs::read_to_string(script_path).map_err(|e| e.to_string())?;
+    let absolute = std::fs::canonicalize(script_path).map_err(|e| e.to_string())?;
+    let specifier = deno_core::ModuleSpecifier::from_file_path(&absolute)
+        .map_err(|_| format!("invalid script path {}", script_path.display()))?;
+    let parsed = deno_ast::parse_module(ParseParams {
+        specifier,
+        text: Arc::from(code),
+        media_type: MediaType::from_path(script_path),
+        capture_tokens: false,
+        scope_analysis: false,
+        maybe_syntax: None,
+    })
+    .map_err(|e| e.to_string())?;
+    let ProgramRef::Module(module) = parsed.program_ref() else {
+        return Ok(None);
+    };
+    for item in &module.body {
+        let ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(export)) = item else {
+            continue;
+        };
+        let Decl::Var(var) = &export.decl else {
+            continue;
+        };
+        for decl in &var.decls {
+            if let Pat::Ident(ident) = &decl.name
+                && ident.id.sym == "schema"
+            {
+                let init = decl.init.as_ref().ok_or("exported schema has no initializer")?;
+                return literal_to_json(init).map(Some);
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// 脚本旁边的 `<name>.schema.json`
+fn sidecar_path(script_path: &Path) -> PathBuf {
+    script_path.with_extension("schema.json")
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+struct CacheEntry {
+    /// 脚本和 sidecar 文件的修改时间，任一变化时重新加载
+    stamp: (Option<SystemTime>, Option<SystemTime>),
+    schema: Result<Option<Arc<ParamSchema>>, String>,
+}
+
+/// 参数 schema 缓存 - 单一职责：加载方法的参数 schema 并在调用前校验参数
+#[derive(Default)]
+pub struct SchemaCache {
+    entries: Mutex<HashMap<PathBuf, CacheEntry>>,
+}
+
+impl SchemaCache {
+    /// 全局缓存（单例模式）
+    pub fn global() -> &'static SchemaCache {
+        static CACHE: OnceLock<SchemaCache> = OnceLock::new();
+        CACHE.get_or_init(SchemaCache::default)
+    }
+
+    /// 加载脚本的参数 schema：优先使用 sidecar 文件，其次是脚本导出的 `schema`
+    pub fn load(&self, script_path: &Path) -> Result<Option<Arc<ParamSchema>>, String> {
+        let sidecar = sidecar_path(script_path);
+        let stamp = (modified(script_path), modified(&sidecar));
+        let mut entries = self.entries.lock().unwrap();
+        if let Some(entry) = entries.get(script_path)
+            && entry.stamp == stamp
+        {
+            return entry.schema.clone();
+        }
+
+        let schema = if stamp.1.is_some() {
+            std::fs::read_to_string(&sidecar)
+                .map_err(|e| e.to_string())
+                .and_then(|content| serde_json::from_str::<Value>(&content).map_err(|e| e.to_string()))
+                .map(Some)
+        } else {
+            exported_schema(script_path)
+        }
+        .and_then(|schema| schema.map(ParamSchema::compile).transpose())
+        .map(|schema| schema.map(Arc::new));
+        if let Err(e) = &schema {
+            tracing::error!("invalid params schema for {}: {}", script_path.display(), e);
+        }
+        entries.insert(script_path.to_path_buf(), CacheEntry {
+            stamp,
+            schema: schema.clone(),
+        });
+        schema
+    }
+
+    /// 按方法的 schema 校验参数；省略的参数按 `null` 校验，没有 schema 的方法不校验
+    pub fn validate(&self, script_path: &Path, params: Option<&Value>) -> Result<(), JsonRpcError> {
+        let schema = self
+            .load(script_path)
+            .map_err(|_| JsonRpcError::internal_error("Invalid params schema"))?;
+        let Some(schema) = schema else {
+            return Ok(());
+        };
+        let violations = schema.validate(params.unwrap_or(&Value::Null));
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(JsonRpcError::invalid_params_violations(&violations))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(violations: &[Violation]) -> Vec<&str> {
+        let mut paths: Vec<&str> = violations.iter().map(|v| v.path.as_str()).collect();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn test_validate_params() {
+        let schema = ParamSchema::compile(json!({
+            "type": "object",
+            "properties": {
+                "a": { "type": "number" },
+                "b": { "type": "integer", "minimum": 0 },
+                "tags": { "type": "array", "items": { "type": "string", "minLength": 1 }, "maxItems": 2 },
+                "mode": { "enum": ["fast", "slow"] }
+            },
+            "required": ["a", "b"],
+            "additionalProperties": false
+        }))
+        .unwrap();
+
+        assert!(schema.validate(&json!({ "a": 1.5, "b": 2, "tags": ["x"], "mode": "fast" })).is_empty());
+        let violations = schema.validate(&json!({ "b": -1, "tags": ["", "y", "z"], "mode": "slower", "c": 1 }));
+        assert_eq!(paths(&violations), vec!["", "", "/b", "/mode", "/tags", "/tags/0"]);
+
+        let violations = schema.validate(&Value::Null);
+        assert_eq!(paths(&violations), vec![""]);
+        assert!(violations[0].message.contains("object"), "{}", violations[0].message);
+    }
+
+    #[test]
+    fn test_full_draft() {
+        let schema = ParamSchema::compile(json!({
+            "$defs": { "name": { "type": "string", "pattern": "^[a-z]+$" } },
+            "type": "object",
+            "properties": { "name": { "$ref": "#/$defs/name" } },
+            "if": { "required": ["name"] },
+            "then": { "required": ["age"] }
+        }))
+        .unwrap();
+        assert!(schema.validate(&json!({ "name": "ada", "age": 36 })).is_empty());
+        assert_eq!(paths(&schema.validate(&json!({ "name": "Ada", "age": 36 }))), vec!["/name"]);
+        assert_eq!(schema.validate(&json!({ "name": "ada" })).len(), 1);
+
+        assert!(ParamSchema::compile(json!({ "required": "a" })).is_err());
+        assert!(ParamSchema::compile(json!(true)).is_ok());
+    }
+
+    #[test]
+    fn test_combinators() {
+        let schema = ParamSchema::compile(json!({
+            "oneOf": [{ "type": "string" }, { "type": "integer" }],
+            "not": { "const": 0 }
+        }))
+        .unwrap();
+        assert!(schema.validate(&json!("x")).is_empty());
+        assert!(schema.validate(&json!(3)).is_empty());
+        assert_eq!(schema.validate(&json!(true)).len(), 1);
+        assert_eq!(schema.validate(&json!(0)).len(), 1);
+    }
+
+    #[test]
+    fn test_load_schema() {
+        // sidecar 文件
+        let schema = SchemaCache::global().load(Path::new("./scripts/rpc/add.js")).unwrap().unwrap();
+        assert!(schema.validate(&json!({ "a": 1, "b": 2 })).is_empty());
+        assert_eq!(schema.validate(&json!({ "a": "1" })).len(), 2);
+
+        // 导出的 schema
+        let schema = SchemaCache::global()
+            .load(Path::new("./scripts/rpc/math/sum.ts"))
+            .unwrap()
+            .unwrap();
+        assert!(schema.validate(&json!({ "numbers": [1, 2.5] })).is_empty());
+        assert_eq!(schema.validate(&json!({ "numbers": [1, "2"] }))[0].path, "/numbers/1");
+
+        assert!(SchemaCache::global().load(Path::new("./scripts/hello.js")).unwrap().is_none());
+
+        let err = SchemaCache::global()
+            .validate(Path::new("./scripts/rpc/add.js"), Some(&json!({ "a": 1 })))
+            .unwrap_err();
+        assert_eq!(err.code, -32602);
+        assert_eq!(
+            err.data.unwrap()["violations"][0],
+            json!({ "path": "", "message": "\"b\" is a required property" })
+        );
+    }
+}
@@ -1,8 +1,12 @@
-use crate::js_bridge::models::{JsonRpcError, JsonRpcRequest};
+use crate::auth::{AuthIdentity, RpcAcl};
+use crate::js_bridge::models::{JsonRpcError, JsonRpcRequest, JsonRpcResponse};
+use crate::request_id::RequestId;
 use axum::body::Body;
 use axum::extract::Request;
 use axum::http::header;
+use serde_json::Value;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 /// 请求解析器 - 单一职责：解析HTTP请求并提取JSON-RPC数据
 pub struct RequestParser;
@@ -10,7 +14,7 @@ pub struct RequestParser;
 impl RequestParser {
     /// 解析HTTP请求，提取headers和body
     pub async fn parse_http_request(req: Request<Body>) -> Result<ParsedHttpRequest, JsonRpcError> {
-        let (parts, body) = req.into_parts();
+        let (mut parts, body) = req.into_parts();
 
         // 验证Content-Type
         let content_type = parts
@@ -37,23 +41,37 @@ impl RequestParser {
         Ok(ParsedHttpRequest {
             body: body_str,
             headers,
+            user: parts.extensions.remove::<AuthIdentity>(),
+            acl: parts.extensions.remove::<Arc<RpcAcl>>(),
+            request_id: parts.extensions.remove::<RequestId>().map(|id| id.0),
         })
     }
 
     /// 解析JSON-RPC请求（单个或批量）
-    pub fn parse_json_rpc_request(body: &str) -> Result<JsonRpcRequestType, JsonRpcError> {
-        // 尝试解析为单个请求
-        if let Ok(req) = serde_json::from_str::<JsonRpcRequest>(body) {
-            return Ok(JsonRpcRequestType::Single(req));
-        }
-
-        // 尝试解析为批量请求
-        if let Ok(batch) = serde_json::from_str::<Vec<JsonRpcRequest>>(body) {
-            return Ok(JsonRpcRequestType::Batch(batch));
+    ///
+    /// 不是合法 JSON 时返回 `Parse error`；单个请求不是合法的请求对象时返回 `Invalid Request`，
+    /// 批量请求中不合法的元素各自对应一个 `Invalid Request` 响应，不影响其余元素。
+    pub fn parse_json_rpc_request(body: &str) -> Result<JsonRpcRequestType, RequestError> {
+        let value = serde_json::from_str::<Value>(body).map_err(|_| RequestError {
+            error: Box::new(JsonRpcError::parse_error("Failed to parse JSON-RPC request")),
+            id: Value::Null,
+        })?;
+        match value {
+            Value::Array(items) => Ok(JsonRpcRequestType::Batch(items.into_iter().map(Self::parse_entry).collect())),
+            value => Self::parse_entry(value).map(JsonRpcRequestType::Single),
         }
+    }
 
-        // 解析失败
-        Err(JsonRpcError::parse_error("Failed to parse JSON-RPC request"))
+    /// 解析单个请求对象
+    fn parse_entry(value: Value) -> Result<JsonRpcRequest, RequestError> {
+        let id = match value.get("id") {
+            Some(id @ (Value::String(_) | Value::Number(_))) => id.clone(),
+            _ => Value::Null,
+        };
+        serde_json::from_value::<JsonRpcRequest>(value).map_err(|e| RequestError {
+            error: Box::new(JsonRpcError::invalid_request(&e.to_string())),
+            id,
+        })
     }
 
     /// 从HTTP headers提取HashMap
@@ -73,13 +91,33 @@ impl RequestParser {
 pub struct ParsedHttpRequest {
     pub body: String,
     pub headers: HashMap<String, String>,
+    /// 认证中间件写入的身份
+    pub user: Option<AuthIdentity>,
+    /// 认证中间件写入的方法访问控制列表
+    pub acl: Option<Arc<RpcAcl>>,
+    /// 请求 ID 中间件写入的 ID
+    pub request_id: Option<String>,
 }
 
 /// JSON-RPC请求类型（单个或批量）
 #[derive(Debug)]
 pub enum JsonRpcRequestType {
     Single(JsonRpcRequest),
-    Batch(Vec<JsonRpcRequest>),
+    /// 每个元素为解析出的请求，或不合法元素的错误
+    Batch(Vec<Result<JsonRpcRequest, RequestError>>),
+}
+
+/// 无法解析的请求：错误和原请求的 `id`（无法取得时为 `null`），总是需要回复
+#[derive(Debug)]
+pub struct RequestError {
+    pub error: Box<JsonRpcError>,
+    pub id: Value,
+}
+
+impl RequestError {
+    pub fn into_response(self) -> JsonRpcResponse {
+        JsonRpcResponse::error(*self.error, Some(self.id))
+    }
 }
 
 #[cfg(test)]
@@ -157,6 +195,27 @@ mod tests {
         let body = r#"invalid json"#;
         let result = RequestParser::parse_json_rpc_request(body);
         assert!(result.is_err());
-        assert_eq!(result.unwrap_err().code, -32700);
+        let err = result.unwrap_err();
+        assert_eq!(err.error.code, -32700);
+        assert_eq!(err.id, serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_parse_invalid_request() {
+        let err = RequestParser::parse_json_rpc_request(r#"{"jsonrpc":"2.0","method":1,"id":7}"#).unwrap_err();
+        assert_eq!(err.error.code, -32600);
+        assert_eq!(err.id, serde_json::json!(7));
+
+        let body = r#"[1, {"jsonrpc":"2.0","method":"add","id":null}, {"jsonrpc":"2.0","method":"add"}]"#;
+        let JsonRpcRequestType::Batch(entries) = RequestParser::parse_json_rpc_request(body).unwrap() else {
+            panic!("Expected batch request");
+        };
+        assert_eq!(entries.len(), 3);
+        let invalid = entries[0].as_ref().unwrap_err();
+        assert_eq!(invalid.error.code, -32600);
+        assert_eq!(invalid.id, serde_json::Value::Null);
+        // "id": null 不是通知，省略 id 才是
+        assert_eq!(entries[1].as_ref().unwrap().id, Some(serde_json::Value::Null));
+        assert_eq!(entries[2].as_ref().unwrap().id, None);
     }
 }
\ No newline at end of file
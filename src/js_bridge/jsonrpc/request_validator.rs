@@ -16,20 +16,24 @@ impl RequestValidator {
             return Err(JsonRpcError::invalid_request("method is required"));
         }
 
+        // 验证参数为对象或数组
+        if let Some(params) = &req.params
+            && !params.is_object()
+            && !params.is_array()
+        {
+            return Err(JsonRpcError::invalid_request("params must be an object or an array"));
+        }
+
         Ok(())
     }
 
-    /// 验证脚本文件是否存在
-    pub fn validate_script_exists(method: &str) -> Result<(), JsonRpcError> {
-        let script_path = format!("./scripts/{}.js", method);
-        if !std::path::Path::new(&script_path).exists() {
-            return Err(JsonRpcError::method_not_found(method));
-        }
-        Ok(())
+    /// 是否为通知：格式合法且省略了 `id`，通知无论成功与否都不回复
+    pub fn is_notification(req: &JsonRpcRequest) -> bool {
+        req.id.is_none() && Self::validate_request(req).is_ok()
     }
 
     /// 验证批量请求不为空
-    pub fn validate_batch_not_empty(requests: &[JsonRpcRequest]) -> Result<(), JsonRpcError> {
+    pub fn validate_batch_not_empty<T>(requests: &[T]) -> Result<(), JsonRpcError> {
         if requests.is_empty() {
             return Err(JsonRpcError::invalid_request("Batch request cannot be empty"));
         }
@@ -75,17 +79,23 @@ mod tests {
     }
 
     #[test]
-    fn test_validate_script_exists() {
-        // 假设add.js存在
-        let result = RequestValidator::validate_script_exists("add");
-        assert!(result.is_ok());
+    fn test_validate_params_structure() {
+        let mut req = create_test_request("add");
+        req.params = Some(json!([1, 2]));
+        assert!(RequestValidator::validate_request(&req).is_ok());
+        req.params = Some(json!(3));
+        assert_eq!(RequestValidator::validate_request(&req).unwrap_err().code, -32600);
     }
 
     #[test]
-    fn test_validate_script_not_exists() {
-        let result = RequestValidator::validate_script_exists("nonexistent");
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err().code, -32601);
+    fn test_is_notification() {
+        let mut req = create_test_request("add");
+        assert!(!RequestValidator::is_notification(&req));
+        req.id = None;
+        assert!(RequestValidator::is_notification(&req));
+        // 不合法的请求即使没有 id 也要回复错误
+        req.jsonrpc = "1.0".to_string();
+        assert!(!RequestValidator::is_notification(&req));
     }
 
     #[test]
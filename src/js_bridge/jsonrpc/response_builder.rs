@@ -13,23 +13,15 @@ impl ResponseBuilder {
     }
 
     /// 构建批量响应
+    ///
+    /// 通知在 `BatchProcessor` 中已经去掉，这里的响应都要返回，包括 `id` 为 `null` 的错误。
     pub fn build_batch_response(responses: Vec<JsonRpcResponse>) -> Response {
-        // 过滤掉通知请求（没有 id 的请求）
-        let filtered_responses: Vec<JsonRpcResponse> = responses
-            .into_iter()
-            .filter(|r| r.id.is_some())
-            .collect();
-
-        // 如果所有请求都是通知，返回空数组
-        if filtered_responses.is_empty() {
-            return Response::builder()
-                .status(StatusCode::OK)
-                .header("Content-Type", "application/json")
-                .body(Body::from("[]"))
-                .unwrap();
+        // 如果所有请求都是通知，不返回响应体
+        if responses.is_empty() {
+            return Self::build_empty_response();
         }
 
-        let body = serde_json::to_string(&filtered_responses).unwrap();
+        let body = serde_json::to_string(&responses).unwrap();
         Response::builder()
             .status(StatusCode::OK)
             .header("Content-Type", "application/json")
@@ -37,9 +29,14 @@ impl ResponseBuilder {
             .unwrap()
     }
 
-    /// 构建错误响应
+    /// 构建错误响应，无法确定请求 id 时 `id` 为 `null`
     pub fn build_error_response(error: JsonRpcError) -> Response {
-        JsonRpcResponse::error(error, None).into_response()
+        JsonRpcResponse::error(error, Some(serde_json::Value::Null)).into_response()
+    }
+
+    /// 通知没有响应：204 No Content
+    pub fn build_empty_response() -> Response {
+        StatusCode::NO_CONTENT.into_response()
     }
 }
 
@@ -66,31 +63,27 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_build_batch_response_with_notifications() {
+    async fn test_build_batch_response_keeps_null_ids() {
         let responses = vec![
             JsonRpcResponse::success(json!({"result": 3}), Some(json!(1))),
-            JsonRpcResponse::success(json!({"result": 12}), None), // 通知
-            JsonRpcResponse::success(json!({"result": 30}), Some(json!(2))),
+            JsonRpcResponse::error(JsonRpcError::invalid_request("jsonrpc version must be 2.0"), Some(json!(null))),
         ];
         let response = ResponseBuilder::build_batch_response(responses);
         assert_eq!(response.status(), StatusCode::OK);
 
         let body = axum::body::to_bytes(response.into_body(), 1024).await.unwrap();
-        let parsed: Vec<JsonRpcResponse> = serde_json::from_slice(&body).unwrap();
-        assert_eq!(parsed.len(), 2); // 通知被过滤掉
+        let parsed: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(parsed.as_array().unwrap().len(), 2);
+        assert_eq!(parsed[1]["id"], json!(null));
     }
 
     #[tokio::test]
     async fn test_build_batch_response_all_notifications() {
-        let responses = vec![
-            JsonRpcResponse::success(json!({"result": 3}), None),
-            JsonRpcResponse::success(json!({"result": 12}), None),
-        ];
-        let response = ResponseBuilder::build_batch_response(responses);
-        assert_eq!(response.status(), StatusCode::OK);
+        let response = ResponseBuilder::build_batch_response(Vec::new());
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
 
         let body = axum::body::to_bytes(response.into_body(), 1024).await.unwrap();
-        assert_eq!(&body[..], b"[]");
+        assert!(body.is_empty());
     }
 
     #[test]
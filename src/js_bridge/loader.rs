@@ -3,6 +3,7 @@ use deno_core::{
     ModuleLoadOptions, ModuleLoadResponse, ModuleLoader, ModuleSource, ModuleSourceCode,
     ModuleSpecifier, ModuleType, ResolutionKind,
 };
+use crate::metrics::Metrics;
 use std::sync::Arc;
 
 pub struct TsModuleLoader;
@@ -25,6 +26,7 @@ impl ModuleLoader for TsModuleLoader {
     ) -> ModuleLoadResponse {
         let module_specifier = module_specifier.clone();
         let fut = async move {
+            let started = std::time::Instant::now();
             let path = module_specifier
                 .to_file_path()
                 .map_err(|_| deno_error::JsErrorBox::generic("Only file:// URLs are supported"))?;
@@ -62,6 +64,12 @@ impl ModuleLoader for TsModuleLoader {
                 _ => (code, ModuleType::JavaScript),
             };
 
+            let kind = if media_type == MediaType::JavaScript { "js" } else { "ts" };
+            Metrics::global()
+                .module_load
+                .with_label_values(&[kind])
+                .observe(started.elapsed().as_secs_f64());
+
             Ok(ModuleSource::new(
                 module_type,
                 ModuleSourceCode::String(transpiled_code.into()),
@@ -1,3 +1,5 @@
+use crate::auth::AuthIdentity;
+use crate::js_bridge::jsonrpc::param_schema::Violation;
 use axum::{
     body::Body,
     http::{HeaderName, HeaderValue},
@@ -14,6 +16,12 @@ pub struct JsRequest {
     pub(crate) path: String,
     pub(crate) headers: HashMap<String, String>,
     pub(crate) body: String,
+    /// 认证中间件校验通过的身份
+    #[serde(default)]
+    pub(crate) user: Option<AuthIdentity>,
+    /// 脚本执行 Span 的 W3C traceparent，用于向下游传播
+    #[serde(default)]
+    pub(crate) traceparent: Option<String>,
 }
 impl Resource for JsRequest {
     fn name(&self) -> Cow<'_, str> {
@@ -32,9 +40,23 @@ impl JsRequest {
             path,
             headers,
             body,
+            user: None,
+            traceparent: None,
         }
     }
 
+    /// 设置已认证身份
+    pub fn with_user(mut self, user: Option<AuthIdentity>) -> Self {
+        self.user = user;
+        self
+    }
+
+    /// 设置追踪上下文
+    pub fn with_traceparent(mut self, traceparent: Option<String>) -> Self {
+        self.traceparent = traceparent;
+        self
+    }
+
     pub fn get_method(&self) -> String {
         self.method.clone()
     }
@@ -54,6 +76,14 @@ impl JsRequest {
     pub fn get_header(&self, key: &str) -> Option<String> {
         self.headers.get(key).cloned()
     }
+
+    pub fn get_user(&self) -> Option<AuthIdentity> {
+        self.user.clone()
+    }
+
+    pub fn get_traceparent(&self) -> Option<String> {
+        self.traceparent.clone()
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -61,6 +91,22 @@ pub struct JsResponse {
     pub(crate) status: u16,
     pub(crate) headers: HashMap<String, String>,
     pub(crate) body: String,
+    /// 需要下发的 Set-Cookie 值（每项一个头），只由会话 op 填充，脚本返回的同名字段被忽略
+    #[serde(default, skip_deserializing, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) cookies: Vec<String>,
+    /// 开发模式下捕获的脚本日志（`SCRIPT_LOG_CAPTURE`），只由 console op 填充
+    #[serde(default, skip_deserializing, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) logs: Vec<ScriptLogEntry>,
+    /// 脚本未捕获的 `RpcError`，JSON-RPC 调用按其中的错误码返回
+    #[serde(skip)]
+    pub(crate) rpc_error: Option<JsonRpcError>,
+}
+
+/// 一条脚本日志
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScriptLogEntry {
+    pub level: String,
+    pub message: String,
 }
 
 impl JsResponse {
@@ -69,9 +115,19 @@ impl JsResponse {
             status,
             headers: HashMap::new(),
             body,
+            cookies: Vec::new(),
+            logs: Vec::new(),
+            rpc_error: None,
         }
     }
 
+    /// 脚本抛出 `RpcError` 时的响应，非 JSON-RPC 请求按 500 返回错误信息
+    pub fn rpc_error(error: JsonRpcError) -> Self {
+        let mut res = Self::new(500, error.message.clone());
+        res.rpc_error = Some(error);
+        res
+    }
+
     pub fn internal_error(msg: &str) -> Self {
         Self::new(500, msg.to_string())
     }
@@ -79,6 +135,12 @@ impl JsResponse {
     pub fn not_found(msg: &str) -> Self {
         Self::new(404, msg.to_string())
     }
+
+    pub fn service_unavailable(msg: &str) -> Self {
+        let mut res = Self::new(503, msg.to_string());
+        res.headers.insert("Retry-After".to_string(), "1".to_string());
+        res
+    }
 }
 
 impl IntoResponse for JsResponse {
@@ -91,6 +153,18 @@ impl IntoResponse for JsResponse {
             }
         }
 
+        for cookie in self.cookies {
+            if let Ok(value) = HeaderValue::try_from(cookie) {
+                res_builder = res_builder.header(axum::http::header::SET_COOKIE, value);
+            }
+        }
+
+        if !self.logs.is_empty()
+            && let Ok(value) = HeaderValue::try_from(serde_json::to_string(&self.logs).unwrap_or_default())
+        {
+            res_builder = res_builder.header("x-script-logs", value);
+        }
+
         res_builder
             .body(Body::from(self.body))
             .unwrap()
@@ -104,10 +178,19 @@ pub struct JsonRpcRequest {
     pub method: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub params: Option<serde_json::Value>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    /// 省略时为 `None`（通知）；`"id": null` 为 `Some(Value::Null)`，仍需回复
+    #[serde(default, deserialize_with = "deserialize_id", skip_serializing_if = "Option::is_none")]
     pub id: Option<serde_json::Value>,
 }
 
+/// 只要字段存在就是 `Some`，区分 `"id": null` 与省略 `id`
+fn deserialize_id<'de, D>(deserializer: D) -> Result<Option<serde_json::Value>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    serde_json::Value::deserialize(deserializer).map(Some)
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct JsonRpcResponse {
     pub jsonrpc: String,
@@ -117,6 +200,9 @@ pub struct JsonRpcResponse {
     pub error: Option<JsonRpcError>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<serde_json::Value>,
+    /// 开发模式下捕获的脚本日志（非 JSON-RPC 标准字段）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logs: Option<Vec<ScriptLogEntry>>,
 }
 
 impl JsonRpcResponse {
@@ -126,6 +212,7 @@ impl JsonRpcResponse {
             result: Some(result),
             error: None,
             id,
+            logs: None,
         }
     }
 
@@ -135,8 +222,21 @@ impl JsonRpcResponse {
             result: None,
             error: Some(error),
             id,
+            logs: None,
         }
     }
+
+    /// 错误响应附带 HTTP 请求 ID
+    pub fn with_request_id(mut self, request_id: Option<&str>) -> Self {
+        self.error = self.error.map(|e| e.with_request_id(request_id));
+        self
+    }
+
+    /// 附带脚本日志，没有日志时不输出该字段
+    pub fn with_logs(mut self, logs: Vec<ScriptLogEntry>) -> Self {
+        self.logs = (!logs.is_empty()).then_some(logs);
+        self
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -172,15 +272,48 @@ impl JsonRpcError {
         }
     }
 
-    /*
-        pub fn invalid_params(msg: &str) -> Self {
-            Self {
-                code: -32602,
-                message: "Invalid params".to_string(),
-                data: Some(serde_json::json!(msg)),
-            }
+    pub fn forbidden(method: &str) -> Self {
+        Self {
+            code: -32003,
+            message: "Forbidden".to_string(),
+            data: Some(serde_json::json!(method)),
+        }
+    }
+
+    pub fn rate_limited(retry_after: u64) -> Self {
+        Self {
+            code: -32005,
+            message: "Rate limit exceeded".to_string(),
+            data: Some(serde_json::json!({ "retry_after": retry_after })),
+        }
+    }
+
+    /// 连接上同时执行的调用数达到上限
+    pub fn too_many_calls() -> Self {
+        Self {
+            code: -32005,
+            message: "Too many concurrent calls".to_string(),
+            data: None,
         }
-    */
+    }
+
+    pub fn invalid_params(msg: &str) -> Self {
+        Self {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: Some(serde_json::json!(msg)),
+        }
+    }
+
+    /// 参数未通过 schema 校验，`data.violations` 为 `[{ path, message }]`
+    pub fn invalid_params_violations(violations: &[Violation]) -> Self {
+        Self {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: Some(serde_json::json!({ "violations": violations })),
+        }
+    }
+
     pub fn internal_error(msg: &str) -> Self {
         Self {
             code: -32603,
@@ -188,6 +321,21 @@ impl JsonRpcError {
             data: Some(serde_json::json!(msg)),
         }
     }
+
+    /// 在 `data` 中附带 HTTP 请求 ID，原有的非对象 data 放到 `detail` 字段
+    pub fn with_request_id(mut self, request_id: Option<&str>) -> Self {
+        let Some(request_id) = request_id else {
+            return self;
+        };
+        let mut data = match self.data.take() {
+            Some(serde_json::Value::Object(map)) => map,
+            Some(detail) => serde_json::Map::from_iter([("detail".to_string(), detail)]),
+            None => serde_json::Map::new(),
+        };
+        data.insert("request_id".to_string(), serde_json::json!(request_id));
+        self.data = Some(serde_json::Value::Object(data));
+        self
+    }
 }
 
 impl IntoResponse for JsonRpcResponse {
@@ -1,88 +1,139 @@
-use crate::db_bridge::DbPool;
-use deno_core::{OpState, op2};
-use diesel::pg::Pg;
-use diesel::prelude::*;
-use diesel::row::{Field, NamedRow, Row};
-use serde::{Deserialize, Serialize};
-use serde_json::{Map, Value};
+use crate::db_bridge::backend::DynamicRow;
+use crate::db_bridge::{DbCursor, DbError, DbPool, DbPools, PRIMARY, is_read_only, query_max_rows};
+use crate::telemetry;
+use deno_core::{OpState, Resource, ResourceId, op2};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::rc::Rc;
+use deno_error::JsErrorBox;
 
-/// 动态行 - 用于存储数据库查询结果
-#[derive(Serialize, Deserialize)]
-pub struct DynamicRow(pub Map<String, Value>);
+/// 脚本访问数据库时取不到连接的标记
+///
+/// 写入 OpState；脚本因此未发送响应时，执行器返回 503 而不是 500。
+#[derive(Debug, Clone)]
+pub struct DatabaseUnavailable(pub String);
 
-impl diesel::deserialize::QueryableByName<Pg> for DynamicRow {
-    fn build<'a>(
-        row: &impl NamedRow<'a, Pg>,
-    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
-        use diesel::deserialize::FromSql;
-        let mut map = Map::new();
-        let column_count = row.field_count();
-
-        for i in 0..column_count {
-            let field = Row::get(row, i).ok_or_else(|| "Failed to get field")?;
-            let name = field
-                .field_name()
-                .ok_or_else(|| "Failed to get column name")?;
-
-            let value = if field.is_null() {
-                Value::Null
-            } else {
-                let raw_value = field.value().unwrap();
-                // 尝试多种类型，通过顺序保证优先匹配
-                if let Ok(v) = FromSql::<diesel::sql_types::Integer, Pg>::from_sql(raw_value) {
-                    let v: i32 = v;
-                    Value::Number(serde_json::Number::from(v))
-                } else if let Ok(v) = FromSql::<diesel::sql_types::Text, Pg>::from_sql(raw_value) {
-                    let v: String = v;
-                    Value::String(v)
-                } else if let Ok(v) = FromSql::<diesel::sql_types::BigInt, Pg>::from_sql(raw_value)
-                {
-                    let v: i64 = v;
-                    Value::Number(serde_json::Number::from(v))
-                } else if let Ok(v) = FromSql::<diesel::sql_types::Double, Pg>::from_sql(raw_value)
-                {
-                    let v: f64 = v;
-                    serde_json::Number::from_f64(v)
-                        .map(Value::Number)
-                        .unwrap_or(Value::Null)
-                } else if let Ok(v) = FromSql::<diesel::sql_types::Bool, Pg>::from_sql(raw_value) {
-                    let v: bool = v;
-                    Value::Bool(v)
-                } else {
-                    String::from_utf8(raw_value.as_bytes().to_vec())
-                        .map(Value::String)
-                        .unwrap_or_else(|_| {
-                            Value::String(format!("Binary: {} bytes", raw_value.as_bytes().len()))
-                        })
-                }
-            };
+/// 选择连接池
+///
+/// 脚本通过 `db.use(name)` 指定连接池；未指定时，只读的 `db.query` 按 `DB_READ_POOLS` 轮询路由，
+/// 其余语句使用主库。
+fn resolve_pool(state: &OpState, name: &str, sql: &str, read: bool) -> Result<(String, DbPool), JsErrorBox> {
+    let pools = DbPools::global();
+    let name = match name {
+        "" if read && is_read_only(sql) => pools.next_read_pool().unwrap_or(PRIMARY),
+        "" => PRIMARY,
+        name => name,
+    };
+    let pool = if name == PRIMARY {
+        state.borrow::<DbPool>().clone()
+    } else {
+        pools
+            .get(name)
+            .cloned()
+            .ok_or_else(|| JsErrorBox::type_error(format!("unknown database \"{}\"", name)))?
+    };
+    Ok((name.to_string(), pool))
+}
 
-            map.insert(name.to_string(), value);
-        }
-        Ok(DynamicRow(map))
+/// 把数据库错误转换为脚本可捕获的异常
+///
+/// 取不到连接时记录 [`DatabaseUnavailable`]，异常信息以 `database unavailable` 开头。
+fn into_js_error(state: &mut OpState, name: &str, error: DbError) -> JsErrorBox {
+    if let DbError::Unavailable(e) = &error {
+        tracing::warn!("database {} unavailable: {}", name, e);
+        state.put(DatabaseUnavailable(e.to_string()));
     }
+    JsErrorBox::generic(error.to_string())
 }
 
 /// 数据库相关操作 - 单一职责：处理JavaScript对数据库的访问
+///
+/// `db` 为 `db.use()` 指定的连接池名称，空字符串表示默认连接池。
 #[op2(fast)]
-pub fn op_sql_execute(state: &mut OpState, #[string] sql: String) -> u32 {
-    let pool = state.borrow::<DbPool>();
-    let mut conn = pool.get().expect("Failed to get connection from pool");
-    diesel::sql_query(sql)
-        .execute(&mut conn)
-        .expect("SQL execution failed") as u32
+pub fn op_sql_execute(state: &mut OpState, #[string] sql: String, #[string] db: String) -> Result<u32, JsErrorBox> {
+    let (name, pool) = resolve_pool(state, &db, &sql, false)?;
+    let _span = tracing::info_span!("op_sql_execute", db.system = pool.system(), db.name = %name, db.statement = telemetry::db_statement(&sql)).entered();
+    pool.get()
+        .and_then(|mut conn| conn.execute(&sql))
+        .map(|n| n as u32)
+        .map_err(|e| into_js_error(state, &name, e))
 }
 
 #[op2]
 #[serde]
-pub fn op_sql_query(state: &mut OpState, #[string] sql: String) -> serde_json::Value {
-    let pool = state.borrow::<DbPool>();
-    let mut conn = pool.get().expect("Failed to get connection from pool");
+pub fn op_sql_query(state: &mut OpState, #[string] sql: String, #[string] db: String) -> Result<serde_json::Value, JsErrorBox> {
+    let (name, pool) = resolve_pool(state, &db, &sql, true)?;
+    let _span = tracing::info_span!("op_sql_query", db.system = pool.system(), db.name = %name, db.statement = telemetry::db_statement(&sql)).entered();
+    let rows = pool.get().and_then(|mut conn| match query_max_rows() {
+        Some(max_rows) => conn.query_max(&sql, max_rows),
+        None => conn.query(&sql),
+    });
+    let rows = match rows {
+        Ok(rows) => rows,
+        // SQL 错误沿用原有行为，返回空数组
+        Err(DbError::Query(_)) => Vec::new(),
+        Err(e) => return Err(into_js_error(state, &name, e)),
+    };
+
+    Ok(serde_json::to_value(rows).unwrap())
+}
+
+/// 脚本打开的查询游标，关闭或脚本结束时释放（PostgreSQL 游标同时归还连接）
+struct CursorResource(RefCell<Option<DbCursor>>);
+
+impl Resource for CursorResource {
+    fn name(&self) -> Cow<'_, str> {
+        "sqlCursor".into()
+    }
+}
 
-    let rows = diesel::sql_query(sql)
-        .load::<DynamicRow>(&mut conn)
-        .unwrap_or_default();
+/// 打开游标，返回资源 ID；`params` 按位置绑定到 `$1`、`$2` …
+#[op2]
+#[smi]
+pub fn op_sql_cursor_open(
+    state: &mut OpState,
+    #[string] sql: String,
+    #[serde] params: Vec<serde_json::Value>,
+    #[string] db: String,
+) -> Result<ResourceId, JsErrorBox> {
+    let (name, pool) = resolve_pool(state, &db, &sql, true)?;
+    let _span = tracing::info_span!("op_sql_cursor_open", db.system = pool.system(), db.name = %name, db.statement = telemetry::db_statement(&sql)).entered();
+    let cursor = pool
+        .open_cursor(&sql, params)
+        .map_err(|e| into_js_error(state, &name, e))?;
+    Ok(state.resource_table.add(CursorResource(RefCell::new(Some(cursor)))))
+}
 
-    serde_json::to_value(rows).unwrap()
+/// 读取下一批行，返回空数组表示已读完
+#[op2]
+#[serde]
+pub fn op_sql_cursor_next(
+    state: &mut OpState,
+    #[smi] rid: ResourceId,
+    #[smi] batch_size: u32,
+) -> Result<Vec<DynamicRow>, JsErrorBox> {
+    let resource: Rc<CursorResource> = state
+        .resource_table
+        .get(rid)
+        .map_err(|_| JsErrorBox::generic("cursor is closed"))?;
+    let mut cursor = resource.0.borrow_mut();
+    let cursor = cursor
+        .as_mut()
+        .ok_or_else(|| JsErrorBox::generic("cursor is closed"))?;
+    let _span = tracing::info_span!("op_sql_cursor_next", batch_size).entered();
+    cursor
+        .fetch(batch_size.max(1) as usize)
+        .map_err(|e| into_js_error(state, "cursor", e))
 }
 
+/// 关闭游标，重复关闭时忽略
+#[op2(fast)]
+pub fn op_sql_cursor_close(state: &mut OpState, #[smi] rid: ResourceId) -> Result<(), JsErrorBox> {
+    let Ok(resource) = state.resource_table.take::<CursorResource>(rid) else {
+        return Ok(());
+    };
+    match resource.0.borrow_mut().take() {
+        Some(cursor) => cursor.close().map_err(|e| into_js_error(state, "cursor", e)),
+        None => Ok(()),
+    }
+}
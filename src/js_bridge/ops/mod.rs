@@ -1,6 +1,8 @@
 pub mod db_ops;
 pub mod request_ops;
 pub mod response_ops;
+pub mod session_ops;
+pub mod socket_ops;
 pub mod utility_ops;
 
 // 创建扩展，包含所有操作
@@ -9,6 +11,7 @@ deno_core::extension!(
     ops = [
         // 工具操作
         utility_ops::op_log,
+        utility_ops::op_console,
         utility_ops::op_delay,
         // 响应操作
         response_ops::op_send_response,
@@ -19,10 +22,29 @@ deno_core::extension!(
         request_ops::op_req_headers,
         request_ops::op_req_body,
         request_ops::op_req_get_header,
+        request_ops::op_req_user,
+        request_ops::op_req_traceparent,
         // 数据库操作
         db_ops::op_sql_execute,
-        db_ops::op_sql_query
+        db_ops::op_sql_query,
+        db_ops::op_sql_cursor_open,
+        db_ops::op_sql_cursor_next,
+        db_ops::op_sql_cursor_close,
+        // 会话操作
+        session_ops::op_session_get,
+        session_ops::op_session_set,
+        session_ops::op_session_remove,
+        session_ops::op_session_all,
+        session_ops::op_session_destroy,
+        // WebSocket 操作
+        socket_ops::op_ws_send,
+        socket_ops::op_ws_send_binary,
+        socket_ops::op_ws_close,
+        socket_ops::op_ws_next_event,
+        socket_ops::op_ws_publish,
+        socket_ops::op_ws_broadcast
     ],
+    // init.js 以静态字符串编译进二进制，只能包含 ASCII 字符
     esm_entry_point = "ext:web_runtime/init.js",
     esm = [ dir "src/js_bridge", "init.js" ],
 );
\ No newline at end of file
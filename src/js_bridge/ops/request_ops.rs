@@ -1,3 +1,4 @@
+use crate::auth::AuthIdentity;
 use crate::js_bridge::models::JsRequest;
 use deno_core::{op2, OpState};
 use std::collections::HashMap;
@@ -57,6 +58,26 @@ pub fn op_req_get_header(
     req.get_header(&key)
 }
 
+#[op2]
+#[serde]
+pub fn op_req_user(state: &mut OpState, #[smi] rid: u32) -> Option<AuthIdentity> {
+    let req = state
+        .resource_table
+        .get::<JsRequest>(rid)
+        .expect("Failed to get JsRequest resource");
+    req.get_user()
+}
+
+#[op2]
+#[string]
+pub fn op_req_traceparent(state: &mut OpState, #[smi] rid: u32) -> Option<String> {
+    let req = state
+        .resource_table
+        .get::<JsRequest>(rid)
+        .expect("Failed to get JsRequest resource");
+    req.get_traceparent()
+}
+
 #[op2(fast)]
 pub fn op_req_close(state: &mut OpState, #[smi] rid: u32) {
     if let Ok(resource) = state.resource_table.take_any(rid) {
@@ -1,10 +1,20 @@
 use crate::js_bridge::models::JsResponse;
+use crate::js_bridge::ops::utility_ops::ScriptLogContext;
+use crate::session::Session;
 use deno_core::{op2, OpState};
 use tokio::sync::oneshot;
 
 /// 响应相关操作 - 单一职责：处理JavaScript发送的HTTP响应
 #[op2]
-pub fn op_send_response(state: &mut OpState, #[serde] res: JsResponse) {
+pub fn op_send_response(state: &mut OpState, #[serde] mut res: JsResponse) {
+    // 会话有变化时追加 Set-Cookie
+    if let Some(cookie) = state.try_borrow::<Session>().and_then(|s| s.set_cookie_header()) {
+        res.cookies.push(cookie);
+    }
+    // 开发模式下随响应返回捕获的日志
+    if let Some(log) = state.try_borrow_mut::<ScriptLogContext>() {
+        res.logs = log.take_captured();
+    }
     let tx = state.take::<oneshot::Sender<JsResponse>>();
     let _ = tx.send(res);
 }
@@ -0,0 +1,40 @@
+use crate::session::Session;
+use deno_core::{OpState, op2};
+use serde_json::{Map, Value};
+
+/// 会话相关操作 - 单一职责：处理JavaScript对会话数据的访问
+#[op2]
+#[serde]
+pub fn op_session_get(state: &mut OpState, #[string] key: String) -> Option<Value> {
+    state.try_borrow::<Session>().and_then(|s| s.get(&key))
+}
+
+#[op2]
+pub fn op_session_set(state: &mut OpState, #[string] key: String, #[serde] value: serde_json::Value) {
+    if let Some(session) = state.try_borrow_mut::<Session>() {
+        session.set(key, value);
+    }
+}
+
+#[op2(fast)]
+pub fn op_session_remove(state: &mut OpState, #[string] key: String) {
+    if let Some(session) = state.try_borrow_mut::<Session>() {
+        session.remove(&key);
+    }
+}
+
+#[op2]
+#[serde]
+pub fn op_session_all(state: &mut OpState) -> Map<String, Value> {
+    state
+        .try_borrow::<Session>()
+        .map(|s| s.all())
+        .unwrap_or_default()
+}
+
+#[op2(fast)]
+pub fn op_session_destroy(state: &mut OpState) {
+    if let Some(session) = state.try_borrow_mut::<Session>() {
+        session.destroy();
+    }
+}
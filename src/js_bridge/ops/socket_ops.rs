@@ -0,0 +1,163 @@
+use crate::websocket::WebSocketState;
+use axum::extract::ws::{CloseFrame, Message};
+use deno_core::{OpState, ToJsBuffer, op2};
+use deno_error::JsErrorBox;
+use serde::Serialize;
+use serde_json::json;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tokio::sync::Notify;
+
+/// 客户端未带关闭码（1005）或连接异常断开（1006）时传给 `onClose` 的关闭码
+pub const CLOSE_NO_STATUS: u16 = 1005;
+pub const CLOSE_ABNORMAL: u16 = 1006;
+
+/// 连接事件，由 `op_ws_next_event` 交给脚本
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SocketEvent {
+    Message { data: String },
+    /// 二进制消息，脚本中为 `Uint8Array`
+    Binary { data: ToJsBuffer },
+    Close { code: u16, reason: String },
+}
+
+/// 脚本 WebSocket 连接的收发通道，写入 OpState
+pub struct SocketChannel {
+    /// 客户端发来的事件；`op_ws_next_event` 等待期间暂时取出
+    pub events: Option<mpsc::Receiver<SocketEvent>>,
+    /// 发往客户端的帧，有界队列
+    pub outgoing: mpsc::Sender<Message>,
+    /// 发送队列写满时通知连接以 1008 关闭
+    pub overflow: Arc<Notify>,
+}
+
+fn channel(state: &OpState) -> Result<&SocketChannel, JsErrorBox> {
+    state
+        .try_borrow::<SocketChannel>()
+        .ok_or_else(|| JsErrorBox::type_error("socket is only available in WebSocket scripts"))
+}
+
+/// 写入发送队列；客户端读取过慢导致队列写满时关闭连接
+fn send(state: &OpState, msg: Message) -> Result<(), JsErrorBox> {
+    let channel = channel(state)?;
+    match channel.outgoing.try_send(msg) {
+        Ok(()) => Ok(()),
+        Err(TrySendError::Full(_)) => {
+            channel.overflow.notify_one();
+            Err(JsErrorBox::generic("socket send queue is full"))
+        }
+        Err(TrySendError::Closed(_)) => Err(JsErrorBox::generic("socket is closed")),
+    }
+}
+
+/// WebSocket 相关操作 - 单一职责：在脚本和客户端连接之间收发消息
+#[op2(fast)]
+pub fn op_ws_send(state: &mut OpState, #[string] data: String) -> Result<(), JsErrorBox> {
+    send(state, Message::Text(data.into()))
+}
+
+#[op2(fast)]
+pub fn op_ws_send_binary(state: &mut OpState, #[buffer] data: &[u8]) -> Result<(), JsErrorBox> {
+    send(state, Message::Binary(data.to_vec().into()))
+}
+
+#[op2(fast)]
+pub fn op_ws_close(state: &mut OpState, code: u32, #[string] reason: String) -> Result<(), JsErrorBox> {
+    let code = u16::try_from(code).map_err(|_| JsErrorBox::range_error(format!("invalid close code {}", code)))?;
+    let frame = CloseFrame {
+        code,
+        reason: reason.into(),
+    };
+    // 连接已经断开时关闭是空操作；队列写满时改为以 1008 关闭
+    let channel = channel(state)?;
+    if let Err(TrySendError::Full(_)) = channel.outgoing.try_send(Message::Close(Some(frame))) {
+        channel.overflow.notify_one();
+    }
+    Ok(())
+}
+
+/// 等待下一个连接事件；连接断开后总是返回 `close` 事件
+#[op2(async)]
+#[serde]
+pub async fn op_ws_next_event(state: Rc<RefCell<OpState>>) -> Result<SocketEvent, JsErrorBox> {
+    let mut events = state
+        .borrow_mut()
+        .try_borrow_mut::<SocketChannel>()
+        .and_then(|channel| channel.events.take())
+        .ok_or_else(|| JsErrorBox::generic("socket events are not available"))?;
+
+    let event = events.recv().await.unwrap_or(SocketEvent::Close {
+        code: CLOSE_ABNORMAL,
+        reason: String::new(),
+    });
+    if let Some(channel) = state.borrow_mut().try_borrow_mut::<SocketChannel>() {
+        channel.events = Some(events);
+    }
+    Ok(event)
+}
+
+fn ws_state(state: &OpState) -> Result<Arc<WebSocketState>, JsErrorBox> {
+    state
+        .try_borrow::<Arc<WebSocketState>>()
+        .cloned()
+        .ok_or_else(|| JsErrorBox::generic("WebSocket hub is not available"))
+}
+
+/// 向频道的订阅者发布消息，返回收到消息的连接数
+#[op2]
+pub fn op_ws_publish(state: &mut OpState, #[string] channel: String, #[serde] data: serde_json::Value) -> Result<u32, JsErrorBox> {
+    ws_state(state)?
+        .hub
+        .publish(&channel, data, None)
+        .map(|n| n as u32)
+        .map_err(|e| JsErrorBox::type_error(e.to_string()))
+}
+
+/// 发给 `/ws` 的所有连接，返回收到消息的连接数
+#[op2]
+pub fn op_ws_broadcast(state: &mut OpState, #[serde] data: serde_json::Value) -> Result<u32, JsErrorBox> {
+    let frame = json!({ "type": "broadcast", "data": data }).to_string();
+    Ok(ws_state(state)?.hub.broadcast(frame) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_socket_event_serialization() {
+        let message = SocketEvent::Message { data: "hi".to_string() };
+        assert_eq!(
+            serde_json::to_value(&message).unwrap(),
+            serde_json::json!({ "type": "message", "data": "hi" })
+        );
+        let close = SocketEvent::Close { code: CLOSE_NO_STATUS, reason: String::new() };
+        assert_eq!(
+            serde_json::to_value(&close).unwrap(),
+            serde_json::json!({ "type": "close", "code": 1005, "reason": "" })
+        );
+    }
+
+    #[test]
+    fn test_send_queue_overflow() {
+        let (outgoing, mut rx) = mpsc::channel(1);
+        let (_events_tx, events) = mpsc::channel(1);
+        let overflow = Arc::new(Notify::new());
+        let mut state = OpState::new(None);
+        state.put(SocketChannel {
+            events: Some(events),
+            outgoing,
+            overflow: overflow.clone(),
+        });
+
+        assert!(send(&state, Message::Text("a".into())).is_ok());
+        // 队列写满：报错并通知连接关闭
+        assert!(send(&state, Message::Text("b".into())).is_err());
+        assert!(futures::FutureExt::now_or_never(overflow.notified()).is_some());
+        assert!(rx.try_recv().is_ok());
+    }
+
+}
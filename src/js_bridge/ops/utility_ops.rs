@@ -1,9 +1,99 @@
-use deno_core::op2;
+use crate::js_bridge::models::{JsRequest, ScriptLogEntry};
+use deno_core::{OpState, op2};
+use std::sync::OnceLock;
+
+/// 是否在响应中返回脚本日志（开发模式，`SCRIPT_LOG_CAPTURE=true`）
+fn capture_enabled() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    *ENABLED.get_or_init(|| {
+        dotenvy::dotenv().ok();
+        std::env::var("SCRIPT_LOG_CAPTURE").is_ok_and(|v| v == "true" || v == "1")
+    })
+}
+
+/// 脚本日志上下文 - 作为 tracing 事件的字段，并在开发模式下收集日志
+#[derive(Debug, Default)]
+pub struct ScriptLogContext {
+    /// 脚本路径（HTTP 请求路径或 `/rpc/<method>`）
+    pub script: String,
+    pub request_id: Option<String>,
+    pub rpc_method: Option<String>,
+    capture: bool,
+    captured: Vec<ScriptLogEntry>,
+}
+
+impl ScriptLogContext {
+    pub fn from_request(request: &JsRequest) -> Self {
+        let rpc_method = if request.method == "JSON-RPC" {
+            request.path.strip_prefix("/rpc/").map(str::to_string)
+        } else {
+            None
+        };
+        Self {
+            script: request.path.clone(),
+            request_id: request.get_header("x-request-id"),
+            rpc_method,
+            capture: capture_enabled(),
+            captured: Vec::new(),
+        }
+    }
+
+    /// 取出已捕获的日志
+    pub fn take_captured(&mut self) -> Vec<ScriptLogEntry> {
+        std::mem::take(&mut self.captured)
+    }
+
+    /// 输出一条脚本日志，未知级别按 info 处理
+    pub fn emit(&mut self, level: &str, msg: &str) {
+        let script = self.script.as_str();
+        let request_id = self.request_id.as_deref().unwrap_or("");
+        let rpc_method = self.rpc_method.as_deref().unwrap_or("");
+        let level = match level {
+            "debug" | "trace" => {
+                tracing::debug!(target: "js", script, request_id, rpc_method, "{}", msg);
+                "debug"
+            }
+            "warn" => {
+                tracing::warn!(target: "js", script, request_id, rpc_method, "{}", msg);
+                "warn"
+            }
+            "error" => {
+                tracing::error!(target: "js", script, request_id, rpc_method, "{}", msg);
+                "error"
+            }
+            _ => {
+                tracing::info!(target: "js", script, request_id, rpc_method, "{}", msg);
+                "info"
+            }
+        };
+        if self.capture {
+            self.captured.push(ScriptLogEntry {
+                level: level.to_string(),
+                message: msg.to_string(),
+            });
+        }
+    }
+}
+
+fn emit(state: &mut OpState, level: &str, msg: &str) {
+    match state.try_borrow_mut::<ScriptLogContext>() {
+        Some(log) => log.emit(level, msg),
+        None => ScriptLogContext::default().emit(level, msg),
+    }
+}
 
 /// 工具操作 - 单一职责：提供JavaScript运行时的工具函数
 #[op2(fast)]
-pub fn op_log(#[string] msg: String) {
-    println!("[JS Log]: {}", msg);
+pub fn op_log(state: &mut OpState, #[string] msg: String) {
+    emit(state, "info", &msg);
+}
+
+/// console.log / debug / info / warn / error 的底层实现
+///
+/// init.js 只替换这几个方法，`console.assert`、`console.table` 等沿用运行时自带的实现。
+#[op2(fast)]
+pub fn op_console(state: &mut OpState, #[string] level: String, #[string] msg: String) {
+    emit(state, &level, &msg);
 }
 
 #[op2(async)]
@@ -13,6 +103,9 @@ pub async fn op_delay(ms: u32) {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
     #[test]
     fn test_op_log_exists() {
         // 测试ops函数存在且可以编译
@@ -20,6 +113,28 @@ mod tests {
         assert!(true);
     }
 
+    #[test]
+    fn test_script_log_capture() {
+        let request = JsRequest::new(
+            "JSON-RPC".to_string(),
+            "/rpc/add".to_string(),
+            HashMap::from([("x-request-id".to_string(), "req-1".to_string())]),
+            String::new(),
+        );
+        let mut log = ScriptLogContext::from_request(&request);
+        assert_eq!(log.rpc_method.as_deref(), Some("add"));
+        assert_eq!(log.request_id.as_deref(), Some("req-1"));
+
+        log.capture = true;
+        log.emit("warn", "slow query");
+        log.emit("verbose", "unknown level");
+        let captured = log.take_captured();
+        assert_eq!(captured.len(), 2);
+        assert_eq!(captured[0].level, "warn");
+        assert_eq!(captured[1].level, "info");
+        assert!(log.take_captured().is_empty());
+    }
+
     #[test]
     fn test_op_delay_exists() {
         // 测试ops函数存在且可以编译
@@ -1,7 +1,7 @@
 #[cfg(test)]
 mod tests {
     use serde_json::{Map, Value, Number};
-    use crate::js_bridge::ops::db_ops::DynamicRow;
+    use crate::db_bridge::backend::DynamicRow;
 
     #[test]
     fn test_dynamic_row_serialization() {
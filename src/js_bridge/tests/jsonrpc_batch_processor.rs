@@ -1,7 +1,9 @@
 #[cfg(test)]
 mod tests {
     use crate::js_bridge::jsonrpc::batch_processor::BatchProcessor;
-    use crate::js_bridge::models::JsonRpcRequest;
+    use crate::js_bridge::jsonrpc::context::RpcContext;
+    use crate::js_bridge::jsonrpc::request_parser::RequestError;
+    use crate::js_bridge::models::{JsonRpcError, JsonRpcRequest};
     use serde_json::json;
     use std::collections::HashMap;
 
@@ -25,7 +27,8 @@ mod tests {
             },
         ];
 
-        let responses = BatchProcessor::process_batch(requests, pool, headers).await;
+        let requests = requests.into_iter().map(Ok).collect();
+        let responses = BatchProcessor::process_batch(requests, RpcContext::new(pool, headers)).await;
         assert_eq!(responses.len(), 2);
         assert!(responses[0].result.is_some());
         assert!(responses[1].result.is_some());
@@ -51,9 +54,40 @@ mod tests {
             },
         ];
 
-        let responses = BatchProcessor::process_batch(requests, pool, headers).await;
+        let requests = requests.into_iter().map(Ok).collect();
+        let responses = BatchProcessor::process_batch(requests, RpcContext::new(pool, headers)).await;
         assert_eq!(responses.len(), 2);
         assert!(responses[0].result.is_some());
         assert!(responses[1].error.is_some());
     }
+
+    #[tokio::test]
+    async fn test_process_batch_with_notifications() {
+        let pool = crate::db_bridge::establish_connection_pool();
+        let headers = HashMap::new();
+
+        let requests = vec![
+            Ok(JsonRpcRequest {
+                jsonrpc: "2.0".to_string(),
+                method: "add".to_string(),
+                params: Some(json!({"a": 1, "b": 2})),
+                id: None,
+            }),
+            Err(RequestError {
+                error: Box::new(JsonRpcError::invalid_request("invalid")),
+                id: json!(null),
+            }),
+            Ok(JsonRpcRequest {
+                jsonrpc: "2.0".to_string(),
+                method: "add".to_string(),
+                params: Some(json!({"a": 3, "b": 4})),
+                id: Some(json!(3)),
+            }),
+        ];
+
+        let responses = BatchProcessor::process_batch(requests, RpcContext::new(pool, headers)).await;
+        assert_eq!(responses.len(), 2); // 通知没有响应
+        assert_eq!(responses[0].error.as_ref().unwrap().code, -32600);
+        assert_eq!(responses[1].id, Some(json!(3)));
+    }
 }
@@ -208,4 +208,79 @@ async fn test_json_rpc_invalid_request() {
     assert!(json_res.result.is_none());
     assert!(json_res.error.is_some());
     assert_eq!(json_res.error.unwrap().code, -32600);
-}
\ No newline at end of file
+}
+
+async fn call_rpc(body: serde_json::Value) -> axum::response::Response {
+    let pool = crate::db_bridge::get_test_pool();
+    let ws_state = crate::websocket::create_websocket_state();
+    let req = Request::builder()
+        .method("POST")
+        .uri("/rpc")
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    handle_json_rpc(State((pool.clone(), ws_state)), req).await.into_response()
+}
+
+#[tokio::test]
+async fn test_json_rpc_notifications() {
+    // 单个通知：执行但不回复
+    let response = call_rpc(serde_json::json!({"jsonrpc": "2.0", "method": "add", "params": {"a": 1, "b": 2}})).await;
+    assert_eq!(response.status(), 204);
+
+    // 通知出错同样不回复
+    let response = call_rpc(serde_json::json!({"jsonrpc": "2.0", "method": "non_existent_method"})).await;
+    assert_eq!(response.status(), 204);
+
+    // 全部是通知的批量请求
+    let response = call_rpc(serde_json::json!([
+        {"jsonrpc": "2.0", "method": "add", "params": {"a": 1, "b": 2}},
+        {"jsonrpc": "2.0", "method": "multiply", "params": {"a": 3, "b": 4}}
+    ]))
+    .await;
+    assert_eq!(response.status(), 204);
+    let body_bytes = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    assert!(body_bytes.is_empty());
+}
+
+#[tokio::test]
+async fn test_json_rpc_batch_with_invalid_entries() {
+    let response = call_rpc(serde_json::json!([
+        {"jsonrpc": "2.0", "method": "add", "params": {"a": 1, "b": 2}, "id": 1},
+        1,
+        {"jsonrpc": "2.0", "method": 5, "id": 2},
+        {"jsonrpc": "2.0", "method": "add", "params": {"a": 1, "b": 2}}
+    ]))
+    .await;
+    assert_eq!(response.status(), 200);
+
+    let body_bytes = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let responses: Vec<serde_json::Value> = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(responses.len(), 3);
+    assert_eq!(responses[0]["id"], 1);
+    assert_eq!(responses[0]["result"]["result"], 3);
+    assert_eq!(responses[1]["id"], serde_json::Value::Null);
+    assert_eq!(responses[1]["error"]["code"], -32600);
+    assert_eq!(responses[2]["id"], 2);
+    assert_eq!(responses[2]["error"]["code"], -32600);
+}
+
+#[tokio::test]
+async fn test_json_rpc_script_error() {
+    let response = call_rpc(serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "math.divide",
+        "params": {"a": 1, "b": 0},
+        "id": 5
+    }))
+    .await;
+    assert_eq!(response.status(), 200);
+
+    let body_bytes = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let json_res: JsonRpcResponse = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json_res.id, Some(serde_json::json!(5)));
+    let error = json_res.error.unwrap();
+    assert_eq!(error.code, -32010);
+    assert_eq!(error.message, "Division by zero");
+    assert_eq!(error.data.unwrap()["a"], 1);
+}
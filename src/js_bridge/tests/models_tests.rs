@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 use axum::response::IntoResponse;
-use super::super::models::{JsRequest, JsResponse};
+use super::super::models::{JsRequest, JsResponse, JsonRpcError};
 
 #[tokio::test]
 async fn test_request_getters() {
@@ -11,6 +11,8 @@ async fn test_request_getters() {
         path: "/test".to_string(),
         headers: headers.clone(),
         body: "body".to_string(),
+        user: None,
+        traceparent: None,
     };
 
     assert_eq!(req.get_method(), "GET");
@@ -29,9 +31,31 @@ async fn test_js_response_into_response() {
         status: 201,
         headers,
         body: "created".to_string(),
+        cookies: vec!["a=1; Path=/".to_string(), "b=2; Path=/".to_string()],
+        logs: vec![],
+        rpc_error: None,
     };
 
     let res = js_res.into_response();
     assert_eq!(res.status(), axum::http::StatusCode::CREATED);
     assert_eq!(res.headers().get("X-Custom").unwrap(), "Value");
-}
\ No newline at end of file
+    assert_eq!(res.headers().get_all("set-cookie").iter().count(), 2);
+}
+
+#[test]
+fn test_json_rpc_error_with_request_id() {
+    let err = JsonRpcError::method_not_found("nope").with_request_id(Some("req-1"));
+    assert_eq!(
+        err.data,
+        Some(serde_json::json!({ "detail": "nope", "request_id": "req-1" }))
+    );
+
+    let err = JsonRpcError::rate_limited(3).with_request_id(Some("req-2"));
+    assert_eq!(
+        err.data,
+        Some(serde_json::json!({ "retry_after": 3, "request_id": "req-2" }))
+    );
+
+    let err = JsonRpcError::internal_error("boom").with_request_id(None);
+    assert_eq!(err.data, Some(serde_json::json!("boom")));
+}
@@ -13,6 +13,8 @@ async fn test_op_req_ops_directly() {
         path: "/op-test".to_string(),
         headers: std::collections::HashMap::new(),
         body: "op-body".to_string(),
+        user: None,
+        traceparent: None,
     };
 
     let rid = runtime.op_state().borrow_mut().resource_table.add(js_req);
@@ -31,4 +33,60 @@ async fn test_op_req_ops_directly() {
     );
     let result = runtime.execute_script("<test>", code);
     assert!(result.is_ok());
-}
\ No newline at end of file
+}
+#[tokio::test]
+async fn test_session_ops_set_cookie() {
+    use crate::js_bridge::models::JsResponse;
+    use crate::session::{Session, SessionConfig};
+    use std::sync::Arc;
+    use tokio::sync::oneshot;
+
+    let mut runtime = JsRuntime::new(RuntimeOptions {
+        extensions: vec![web_runtime::init()],
+        ..Default::default()
+    });
+    let (tx, rx) = oneshot::channel::<JsResponse>();
+    let config = Arc::new(SessionConfig::new().with_secret(b"ops-test"));
+    runtime.op_state().borrow_mut().put(Session::new(config));
+    runtime.op_state().borrow_mut().put(tx);
+
+    let code = r#"
+        session.set("user", { id: 7 });
+        if (session.get("user").id !== 7) throw new Error("session get mismatch");
+        if (session.get("missing") !== undefined) throw new Error("expected undefined");
+        Deno.core.ops.op_send_response({ status: 200, headers: {}, body: "ok" });
+    "#;
+    runtime.execute_script("<test_session>", code).unwrap();
+
+    let res = rx.await.unwrap();
+    assert_eq!(res.cookies.len(), 1);
+    assert!(res.cookies[0].starts_with("ujs_session="));
+}
+
+#[tokio::test]
+async fn test_send_response_ignores_script_cookies_and_logs() {
+    use crate::js_bridge::models::JsResponse;
+    use tokio::sync::oneshot;
+
+    let mut runtime = JsRuntime::new(RuntimeOptions {
+        extensions: vec![web_runtime::init()],
+        ..Default::default()
+    });
+    let (tx, rx) = oneshot::channel::<JsResponse>();
+    runtime.op_state().borrow_mut().put(tx);
+
+    let code = r#"
+        Deno.core.ops.op_send_response({
+            status: 200,
+            headers: {},
+            body: "ok",
+            cookies: ["admin=1; Path=/"],
+            logs: [{ level: "info", message: "forged" }],
+        });
+    "#;
+    runtime.execute_script("<test_forged_fields>", code).unwrap();
+
+    let res = rx.await.unwrap();
+    assert!(res.cookies.is_empty());
+    assert!(res.logs.is_empty());
+}
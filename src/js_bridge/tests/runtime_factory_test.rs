@@ -22,6 +22,8 @@ mod tests {
             path: "/test".to_string(),
             headers: HashMap::new(),
             body: "test".to_string(),
+            user: None,
+            traceparent: None,
         };
 
         let pool = crate::db_bridge::establish_connection_pool();
@@ -25,12 +25,15 @@ mod tests {
             path: "/test".to_string(),
             headers: std::collections::HashMap::new(),
             body: String::new(),
+            user: None,
+            traceparent: None,
         };
 
         let config = RuntimeConfig {
             script_path: "./non_existent.js".to_string(),
             request,
             db_pool: pool,
+            ws_state: None,
         };
 
         let response = ScriptExecutor::execute(config).await;
@@ -488,12 +491,15 @@ mod tests {
             path: "/test".to_string(),
             headers: std::collections::HashMap::new(),
             body: String::new(),
+            user: None,
+            traceparent: None,
         };
 
         let config = RuntimeConfig {
             script_path: "scripts/test_execute.js".to_string(),
             request,
             db_pool: pool,
+            ws_state: None,
         };
 
         let response = ScriptExecutor::execute(config).await;
@@ -519,12 +525,15 @@ mod tests {
             path: "/test".to_string(),
             headers: std::collections::HashMap::new(),
             body: String::new(),
+            user: None,
+            traceparent: None,
         };
 
         let config = RuntimeConfig {
             script_path: "scripts/test_execute_error.js".to_string(),
             request,
             db_pool: pool,
+            ws_state: None,
         };
 
         let response = ScriptExecutor::execute(config).await;
@@ -559,12 +568,15 @@ mod tests {
             path: "/test".to_string(),
             headers,
             body: r#"{"test": "data"}"#.to_string(),
+            user: None,
+            traceparent: None,
         };
 
         let config = RuntimeConfig {
             script_path: "scripts/test_execute_post.js".to_string(),
             request,
             db_pool: pool,
+            ws_state: None,
         };
 
         let response = ScriptExecutor::execute(config).await;
@@ -573,6 +585,59 @@ mod tests {
         let _ = fs::remove_file(&test_file);
     }
 
+    fn timeout_config(script_path: &str) -> RuntimeConfig {
+        RuntimeConfig {
+            script_path: script_path.to_string(),
+            request: JsRequest {
+                method: "GET".to_string(),
+                path: "/test".to_string(),
+                headers: std::collections::HashMap::new(),
+                body: String::new(),
+                user: None,
+                traceparent: None,
+            },
+            db_pool: crate::db_bridge::establish_connection_pool(),
+            ws_state: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_script_timeout() {
+        let test_file = "scripts/test_execute_timeout.js";
+        fs::write(test_file, "while (true) {}").unwrap();
+
+        let timeout = std::time::Duration::from_millis(200);
+        let response = ScriptExecutor::execute_with_timeout(timeout_config(test_file), Some(timeout), timeout).await;
+        assert_eq!(response.status, 504);
+
+        let _ = fs::remove_file(test_file);
+    }
+
+    #[tokio::test]
+    async fn test_execute_terminates_script_after_response() {
+        let test_file = "scripts/test_execute_linger.js";
+        let test_content = r#"
+            Deno.core.ops.op_send_response({ status: 200, headers: {}, body: "ok" });
+            while (true) {}
+        "#;
+        fs::write(test_file, test_content).unwrap();
+
+        // 未设置执行超时，响应正常返回
+        let timeouts = crate::metrics::Metrics::global().script_timeouts.get();
+        let linger = std::time::Duration::from_millis(200);
+        let response = ScriptExecutor::execute_with_timeout(timeout_config(test_file), None, linger).await;
+        assert_eq!(response.status, 200);
+
+        // 发送响应后仍在运行的脚本会被终止
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while crate::metrics::Metrics::global().script_timeouts.get() <= timeouts {
+            assert!(std::time::Instant::now() < deadline, "script was not terminated after responding");
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+
+        let _ = fs::remove_file(test_file);
+    }
+
     #[test]
     fn test_run_script_with_invalid_path_characters() {
         let mut runtime =
@@ -0,0 +1,338 @@
+use file_rotate::compression::Compression;
+use file_rotate::suffix::AppendCount;
+use file_rotate::{ContentLimit, FileRotate};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use time::UtcOffset;
+use time::format_description::well_known::Iso8601;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::fmt::time::OffsetTime;
+use tracing_subscriber::layer::{Layer, SubscriberExt};
+use tracing_subscriber::registry::Registry;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, reload};
+
+/// 运行时可替换的日志过滤器句柄（供管理接口调整日志级别）
+pub type FilterHandle = reload::Handle<EnvFilter, Registry>;
+
+/// 过滤器之后、输出层之前的 Subscriber 类型
+type Filtered = tracing_subscriber::layer::Layered<reload::Layer<EnvFilter, Registry>, Registry>;
+
+type BoxedLayer = Box<dyn Layer<Filtered> + Send + Sync>;
+
+type Timer = OffsetTime<Iso8601>;
+
+/// 日志输出格式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogFormat {
+    /// 单行文本（默认）
+    Text,
+    /// 多行文本，适合本地开发
+    Pretty,
+    /// 每行一个 JSON 对象
+    Json,
+}
+
+/// 日志文件切分方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogRotation {
+    Hourly,
+    Daily,
+    Never,
+    /// 按大小切分（字节）
+    Size(u64),
+}
+
+/// 日志配置
+#[derive(Debug, Clone)]
+pub struct LogConfig {
+    /// 初始过滤规则（`RUST_LOG` 语法）
+    pub filter: String,
+    pub format: LogFormat,
+    /// 是否输出到标准输出
+    pub stdout: bool,
+    /// 是否输出到滚动文件
+    pub file: bool,
+    pub dir: String,
+    pub file_name: String,
+    pub rotation: LogRotation,
+    /// 保留的历史日志文件数，None 表示不清理
+    pub max_files: Option<usize>,
+    /// 时间戳使用的时区偏移
+    pub offset: UtcOffset,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            filter: "info".to_string(),
+            format: LogFormat::Text,
+            stdout: false,
+            file: true,
+            dir: "logs".to_string(),
+            file_name: "ujs-web-svr.log".to_string(),
+            rotation: LogRotation::Daily,
+            max_files: None,
+            // 默认北京时间（UTC+8）
+            offset: UtcOffset::from_hms(8, 0, 0).unwrap(),
+        }
+    }
+}
+
+impl LogConfig {
+    /// 从环境变量读取配置
+    pub fn from_env() -> Result<Self, String> {
+        dotenvy::dotenv().ok();
+        let var = |key: &str| std::env::var(key).ok().filter(|v| !v.is_empty());
+        let defaults = Self::default();
+
+        let format = match var("LOG_FORMAT").as_deref() {
+            None | Some("text") => LogFormat::Text,
+            Some("pretty") => LogFormat::Pretty,
+            Some("json") => LogFormat::Json,
+            Some(other) => return Err(format!("Unknown LOG_FORMAT: {}", other)),
+        };
+
+        let (stdout, file) = match var("LOG_SINKS") {
+            Some(sinks) => {
+                let sinks: Vec<&str> = sinks.split(',').map(str::trim).collect();
+                if let Some(unknown) = sinks.iter().find(|s| !matches!(**s, "stdout" | "file")) {
+                    return Err(format!("Unknown log sink: {}", unknown));
+                }
+                (sinks.contains(&"stdout"), sinks.contains(&"file"))
+            }
+            None => (defaults.stdout, defaults.file),
+        };
+
+        let rotation = match var("LOG_ROTATION").as_deref() {
+            None | Some("daily") => LogRotation::Daily,
+            Some("hourly") => LogRotation::Hourly,
+            Some("never") => LogRotation::Never,
+            Some("size") => {
+                let mb: u64 = var("LOG_MAX_SIZE_MB").and_then(|v| v.parse().ok()).unwrap_or(100);
+                LogRotation::Size(mb * 1024 * 1024)
+            }
+            Some(other) => return Err(format!("Unknown LOG_ROTATION: {}", other)),
+        };
+
+        let offset = match var("LOG_TIMEZONE") {
+            Some(tz) => parse_offset(&tz).ok_or_else(|| format!("Invalid LOG_TIMEZONE: {}", tz))?,
+            None => defaults.offset,
+        };
+
+        Ok(Self {
+            filter: var("RUST_LOG").unwrap_or(defaults.filter),
+            format,
+            stdout,
+            file,
+            dir: var("LOG_DIR").unwrap_or(defaults.dir),
+            file_name: var("LOG_FILE_NAME").unwrap_or(defaults.file_name),
+            rotation,
+            max_files: var("LOG_MAX_FILES").and_then(|v| v.parse().ok()),
+            offset,
+        })
+    }
+}
+
+/// 解析 `UTC` 或 `+08:00` / `-05:30` 形式的时区偏移
+fn parse_offset(tz: &str) -> Option<UtcOffset> {
+    if tz.eq_ignore_ascii_case("utc") || tz == "Z" {
+        return Some(UtcOffset::UTC);
+    }
+    let (sign, rest) = match tz.as_bytes().first()? {
+        b'+' => (1, &tz[1..]),
+        b'-' => (-1, &tz[1..]),
+        _ => return None,
+    };
+    let (hours, minutes) = rest.split_once(':').unwrap_or((rest, "0"));
+    let hours: i8 = hours.parse().ok()?;
+    let minutes: i8 = minutes.parse().ok()?;
+    UtcOffset::from_hms(sign * hours, sign * minutes, 0).ok()
+}
+
+/// 初始化后需要保持存活的日志资源
+pub struct Logging {
+    /// 日志级别句柄
+    pub filter: FilterHandle,
+    _guards: Vec<WorkerGuard>,
+}
+
+/// 安装全局 Subscriber：可重载的过滤器 + 各输出层 + 额外的层（如 OpenTelemetry）
+pub fn init<L>(config: &LogConfig, extra: L) -> Result<Logging, String>
+where
+    L: Layer<tracing_subscriber::layer::Layered<Vec<BoxedLayer>, Filtered>> + Send + Sync,
+{
+    let filter = EnvFilter::try_new(&config.filter)
+        .map_err(|e| format!("Invalid log filter {}: {}", config.filter, e))?;
+    let (filter, handle) = reload::Layer::new(filter);
+
+    let timer = OffsetTime::new(config.offset, Iso8601::DEFAULT);
+    let mut layers: Vec<BoxedLayer> = Vec::new();
+    let mut guards = Vec::new();
+
+    if config.stdout {
+        let (writer, guard) = tracing_appender::non_blocking(io::stdout());
+        layers.push(fmt_layer(config.format, writer, timer.clone(), true));
+        guards.push(guard);
+    }
+    if config.file {
+        let writer: Box<dyn Write + Send> = match config.rotation {
+            LogRotation::Size(max_bytes) => Box::new(size_rolling_file(
+                &config.dir,
+                &config.file_name,
+                max_bytes,
+                config.max_files.unwrap_or(5),
+            )?),
+            rotation => {
+                let rotation = match rotation {
+                    LogRotation::Hourly => Rotation::HOURLY,
+                    LogRotation::Never => Rotation::NEVER,
+                    _ => Rotation::DAILY,
+                };
+                let mut builder = RollingFileAppender::builder()
+                    .rotation(rotation)
+                    .filename_prefix(config.file_name.clone());
+                if let Some(max_files) = config.max_files {
+                    builder = builder.max_log_files(max_files);
+                }
+                Box::new(
+                    builder
+                        .build(&config.dir)
+                        .map_err(|e| format!("Failed to open log file: {}", e))?,
+                )
+            }
+        };
+        let (writer, guard) = tracing_appender::non_blocking(writer);
+        layers.push(fmt_layer(config.format, writer, timer, false));
+        guards.push(guard);
+    }
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(layers)
+        .with(extra)
+        .try_init()
+        .map_err(|e| format!("Failed to install log subscriber: {}", e))?;
+
+    Ok(Logging {
+        filter: handle,
+        _guards: guards,
+    })
+}
+
+/// 构建单个输出层
+fn fmt_layer<W>(format: LogFormat, writer: W, timer: Timer, ansi: bool) -> BoxedLayer
+where
+    W: for<'w> tracing_subscriber::fmt::MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer().with_writer(writer);
+    match format {
+        LogFormat::Json => layer
+            .json()
+            .with_current_span(true) // 当前 Span 的字段（如 request_id）
+            .with_span_list(true) // 从根到当前的 Span 链
+            .with_file(true)
+            .with_line_number(true)
+            .with_thread_names(true)
+            .with_timer(timer)
+            .boxed(),
+        LogFormat::Pretty => layer.pretty().with_ansi(ansi).with_timer(timer).boxed(),
+        LogFormat::Text => layer
+            .with_ansi(ansi) // 文件中禁用 ANSI 颜色代码
+            .with_file(true) //打印文件名
+            .with_line_number(true) //打印行号
+            .with_thread_ids(true) //打印线程ID
+            .with_thread_names(true) //打印线程名称
+            .with_target(false) //不打印target
+            .with_timer(timer)
+            .boxed(),
+    }
+}
+
+/// 按大小切分的日志文件：写满后 `name` → `name.1` → `name.2` …，最多保留 `max_files` 个历史文件
+///
+/// 每次写入一整行日志，超过 `max_bytes` 后在行边界切分。
+fn size_rolling_file(dir: &str, file_name: &str, max_bytes: u64, max_files: usize) -> Result<FileRotate<AppendCount>, String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to open log file: {}", e))?;
+    Ok(FileRotate::new(
+        PathBuf::from(dir).join(file_name),
+        AppendCount::new(max_files.max(1)),
+        ContentLimit::BytesSurpassed(max_bytes.max(1) as usize),
+        Compression::None,
+        None,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_json_format() {
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let timer = OffsetTime::new(UtcOffset::UTC, Iso8601::DEFAULT);
+        let (filter, _handle) = reload::Layer::new(EnvFilter::new("info"));
+        let subscriber = tracing_subscriber::registry().with(filter).with(fmt_layer(
+            LogFormat::Json,
+            move || writer.clone(),
+            timer,
+            false,
+        ));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("http_request", request_id = "abc");
+            let _enter = span.enter();
+            tracing::warn!(rows = 3, "slow query");
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let line: serde_json::Value = serde_json::from_str(output.trim()).unwrap();
+        assert_eq!(line["level"], "WARN");
+        assert_eq!(line["fields"]["message"], "slow query");
+        assert_eq!(line["fields"]["rows"], 3);
+        assert_eq!(line["span"]["request_id"], "abc");
+        assert_eq!(line["spans"][0]["name"], "http_request");
+        assert_eq!(line["spans"][0]["request_id"], "abc");
+    }
+
+    #[test]
+    fn test_parse_offset() {
+        assert_eq!(parse_offset("UTC"), Some(UtcOffset::UTC));
+        assert_eq!(parse_offset("+08:00"), UtcOffset::from_hms(8, 0, 0).ok());
+        assert_eq!(parse_offset("-05:30"), UtcOffset::from_hms(-5, -30, 0).ok());
+        assert_eq!(parse_offset("Asia/Shanghai"), None);
+    }
+
+    #[test]
+    fn test_size_rolling_file() {
+        let dir = std::env::temp_dir().join(format!("ujs-log-test-{}", std::process::id()));
+        let dir_str = dir.to_str().unwrap();
+        let mut file = size_rolling_file(dir_str, "app.log", 10, 2).unwrap();
+
+        for _ in 0..4 {
+            file.write_all(b"0123456789").unwrap();
+        }
+
+        assert!(dir.join("app.log").exists());
+        assert!(dir.join("app.log.1").exists());
+        assert!(dir.join("app.log.2").exists());
+        assert!(!dir.join("app.log.3").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,6 +1,15 @@
+mod admin;
+mod auth;
 mod db_bridge;
+mod health;
 mod js_bridge;
+mod logging;
+mod metrics;
+mod rate_limit;
+mod request_id;
+mod session;
 mod static_server;
+mod telemetry;
 mod test_utils;
 mod websocket;
 
@@ -8,43 +17,79 @@ use crate::js_bridge::jsonrpc::handle_json_rpc;
 use crate::static_server::StaticServerConfig;
 use axum::{
     Router,
+    middleware,
     routing::{any, post},
 };
+use std::net::SocketAddr;
+use std::sync::Arc;
 use db_bridge::establish_connection_pool;
 use tower_http::trace::{DefaultOnRequest, DefaultOnResponse, TraceLayer};
-use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
-use time::UtcOffset;
 use tracing::Level;
 
 #[tokio::main]
 async fn main() {
-    // 初始化日志系统,默认级别为 info
-    // 创建 logs 文件夹并按天分割日志
-    let file_appender = tracing_appender::rolling::daily("logs", "ujs-web-svr.log");
-    let (non_blocking, guard) = tracing_appender::non_blocking(file_appender);
+    // 子命令：ujs-web-svr migrate [up | status | down [N]]
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|arg| arg == "migrate") {
+        // 子命令不初始化日志文件，只把日志写到标准错误
+        tracing_subscriber::fmt()
+            .with_writer(std::io::stderr)
+            .with_target(false)
+            .init();
+        if let Err(e) = db_bridge::migrations::run_cli(&args[1..]) {
+            tracing::error!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
 
-    // 配置环境过滤器
-    let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
-    tracing::info!("env_filter: {}",env_filter.to_string());
-    // 设置时区为北京时间（UTC+8）
-    let beijing_offset = UtcOffset::from_hms(8, 0, 0).unwrap();
-    tracing_subscriber::registry()
-        .with(env_filter) // 设置日志级别
-        .with(
-            tracing_subscriber::fmt::layer()
-                .with_writer(non_blocking) // 输出到文件
-                .with_ansi(false) // 禁用 ANSI 颜色代码
-                .with_file(true) //打印文件名
-                .with_line_number(true) //打印行号
-                .with_thread_ids(true) //打印线程ID
-                .with_thread_names(true) //打印线程名称
-                .with_target(false) //不打印target
-                .with_timer(tracing_subscriber::fmt::time::OffsetTime::new(beijing_offset, time::format_description::well_known::Iso8601::DEFAULT)),
-        )
-        .init();
+    health::started_at();
+
+    // 链路追踪（OTEL_TRACES_EXPORTER 未设置时不导出）
+    let telemetry_config = telemetry::TelemetryConfig::from_env().expect("Invalid tracing configuration");
+    let tracer_provider = telemetry_config
+        .build_provider()
+        .expect("Failed to initialize tracing exporter");
+
+    // 初始化日志系统，输出格式、目标、切分方式和时区见 LOG_* 环境变量
+    let log_config = logging::LogConfig::from_env().expect("Invalid log configuration");
+    let logging = logging::init(&log_config, tracer_provider.as_ref().map(telemetry::layer))
+        .expect("Failed to initialize logging");
+    tracing::info!("log filter: {}, format: {:?}", log_config.filter, log_config.format);
+    tracing::info!("trace exporter: {:?}", telemetry_config.exporter);
 
+    // 数据库不可用时照常启动，后台按退避间隔重连
     let pool = establish_connection_pool();
+    // 启动迁移在后台执行并随连接恢复重试，完成前 /readyz 返回 503
+    if db_bridge::migrations::run_on_startup() {
+        db_bridge::migrations::spawn_on_startup(pool.clone());
+    }
+    db_bridge::spawn_connection_monitor(pool.clone());
+    let db_pools = db_bridge::DbPools::global();
+    tracing::info!("named database pools: {:?}", db_pools.iter().map(|(name, _)| name).collect::<Vec<_>>());
     let ws_state = websocket::create_websocket_state();
+    // Postgres NOTIFY 转发到 WebSocket 频道（未设置 PG_LISTEN_CHANNELS 时不启动）
+    if let Some(listen_config) = websocket::pg_listen::ListenConfig::from_env().expect("Invalid PG_LISTEN configuration") {
+        websocket::pg_listen::spawn_listener(listen_config, pool.clone(), ws_state.clone());
+    }
+    let auth_config = Arc::new(auth::AuthConfig::from_env().expect("Invalid auth configuration"));
+    tracing::info!("auth enabled: {}", auth_config.enabled);
+
+    // 管理接口默认不挂载，启用后仍要求 admin 角色
+    let admin_router = if admin::enabled_from_env() {
+        if !auth_config.enabled {
+            tracing::warn!("ADMIN_ENABLED is set but auth is disabled, /admin requests will be rejected");
+        }
+        admin::router(logging.filter.clone())
+    } else {
+        Router::new()
+    };
+    let rate_limiter = Arc::new(rate_limit::RateLimiter::new(rate_limit::RateLimitConfig::from_env()));
+    tracing::info!("rate limit enabled: {}", rate_limiter.is_enabled());
+    tracing::info!(
+        "JSON-RPC methods: {:?}",
+        js_bridge::jsonrpc::method_registry::MethodRegistry::global().methods()
+    );
 
     // 配置静态服务器
     let static_config = StaticServerConfig::new()
@@ -66,13 +111,13 @@ async fn main() {
         .with_cors(true)
         .with_trace(false);
 
-    let static_router = static_config.build_router();
+    let static_router = static_config
+        .build_router()
+        .layer(middleware::from_fn(metrics::track_static));
 
     let trace_layer = TraceLayer::new_for_http()
-        // 1. 在 Span 里带上 “method + uri”
-        // .make_span_with(DefaultMakeSpan::new()
-        //     .level(Level::INFO)
-        //     .include_headers(false))
+        // 1. 在 Span 里带上 “method + uri”，并接续上游的 traceparent
+        .make_span_with(telemetry::make_http_span)
         // 2. 请求到达时打印一行
         .on_request(DefaultOnRequest::new().level(Level::INFO))
         // 3. 响应返回时打印一行（含 status + latency）
@@ -83,15 +128,37 @@ async fn main() {
         .route("/js/{*script_path}", any(js_bridge::handle_js_script))
         .route("/rpc", post(handle_json_rpc))
         .route("/ws", axum::routing::get(websocket::handle_websocket))
-        .with_state((pool, ws_state))
+        .route("/ws/{*script_path}", axum::routing::get(websocket::handle_script_websocket))
+        .route("/events", axum::routing::get(websocket::sse::handle_events))
+        .with_state((pool.clone(), ws_state.clone()))
+        .merge(admin_router)
+        // 限流在认证之后执行，以便按已认证身份计数
+        .layer(middleware::from_fn_with_state(rate_limiter, rate_limit::rate_limit))
+        // 认证只作用于 /js、/rpc、/ws、/events 和 /admin，静态资源不受影响
+        .layer(middleware::from_fn_with_state(auth_config, auth::require_auth))
+        .merge(
+            Router::new()
+                .route("/metrics", axum::routing::get(metrics::metrics_handler))
+                .merge(health::router())
+                .with_state((pool, ws_state)),
+        )
         .merge(static_router)
-        .layer(trace_layer);
+        .layer(trace_layer)
+        // 最外层：先确定请求 ID，TraceLayer 的 Span 和所有响应都能带上
+        .layer(middleware::from_fn(request_id::propagate));
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3001").await.unwrap();
     tracing::info!("rust_demo listening on {}", listener.local_addr().unwrap());
     println!("listening on {}", listener.local_addr().unwrap());
 
     // 保持 guard 存活,确保日志写入器不被关闭
-    let _guard = guard;
-    axum::serve(listener, app).await.unwrap();
+    let _logging = logging;
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .await
+        .unwrap();
+
+    // 导出尚未发送的 Span
+    if let Some(provider) = tracer_provider {
+        let _ = provider.shutdown();
+    }
 }
@@ -0,0 +1,286 @@
+use crate::db_bridge::DbPool;
+use crate::js_bridge::executor::worker_pool::WorkerPool;
+use axum::{
+    extract::{Request, State},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge,
+    IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+/// 毫秒到十秒量级的延迟分桶
+const LATENCY_BUCKETS: &[f64] = &[
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+/// 全部 Prometheus 指标
+pub struct Metrics {
+    registry: Registry,
+    /// 脚本 HTTP 请求数（path, status）
+    pub script_requests: IntCounterVec,
+    /// 脚本 HTTP 请求耗时（path）
+    pub script_duration: HistogramVec,
+    /// JSON-RPC 调用数（method, outcome）
+    pub rpc_requests: IntCounterVec,
+    /// JSON-RPC 调用耗时（method）
+    pub rpc_duration: HistogramVec,
+    /// 脚本执行错误（kind: load / exception / no_response / rejected）
+    pub script_errors: IntCounterVec,
+    /// 脚本执行超时
+    pub script_timeouts: IntCounter,
+    /// 创建 JsRuntime 的耗时
+    pub isolate_create: Histogram,
+    /// 模块加载（含 TS 转译）耗时（kind: js / ts）
+    pub module_load: HistogramVec,
+    /// 脚本线程池状态（state: active / queued）
+    pub worker_pool: IntGaugeVec,
+    /// 数据库连接池状态（state: idle / in_use / max）
+    pub db_pool: IntGaugeVec,
+    /// 获取数据库连接的等待时间
+    pub db_pool_wait: Histogram,
+    /// 获取数据库连接超时次数
+    pub db_pool_timeouts: IntCounter,
+    /// 当前 WebSocket 连接数
+    pub ws_connections: IntGauge,
+    /// 当前打开的 SSE 连接数
+    pub sse_connections: IntGauge,
+    /// 因接收端落后而丢弃的广播消息数
+    pub ws_lagged_messages: IntCounter,
+    /// 静态资源请求数（prefix, status）
+    pub static_requests: IntCounterVec,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("ujs".to_string()), None)
+            .expect("Failed to create metrics registry");
+
+        let histogram = |name: &str, help: &str| {
+            HistogramOpts::new(name, help).buckets(LATENCY_BUCKETS.to_vec())
+        };
+
+        let metrics = Self {
+            script_requests: IntCounterVec::new(
+                Opts::new("script_requests_total", "HTTP script requests"),
+                &["path", "status"],
+            )
+            .unwrap(),
+            script_duration: HistogramVec::new(
+                histogram("script_request_duration_seconds", "HTTP script request latency"),
+                &["path"],
+            )
+            .unwrap(),
+            rpc_requests: IntCounterVec::new(
+                Opts::new("rpc_requests_total", "JSON-RPC calls"),
+                &["method", "outcome"],
+            )
+            .unwrap(),
+            rpc_duration: HistogramVec::new(
+                histogram("rpc_request_duration_seconds", "JSON-RPC call latency"),
+                &["method"],
+            )
+            .unwrap(),
+            script_errors: IntCounterVec::new(
+                Opts::new("script_errors_total", "Script execution errors"),
+                &["kind"],
+            )
+            .unwrap(),
+            script_timeouts: IntCounter::new("script_timeouts_total", "Script execution timeouts")
+                .unwrap(),
+            isolate_create: Histogram::with_opts(histogram(
+                "isolate_create_seconds",
+                "Time to create a JsRuntime",
+            ))
+            .unwrap(),
+            module_load: HistogramVec::new(
+                histogram("module_load_seconds", "Module load and transpile time"),
+                &["kind"],
+            )
+            .unwrap(),
+            worker_pool: IntGaugeVec::new(
+                Opts::new("worker_pool_scripts", "Scripts in the worker pool"),
+                &["state"],
+            )
+            .unwrap(),
+            db_pool: IntGaugeVec::new(
+                Opts::new("db_pool_connections", "Database pool connections"),
+                &["state"],
+            )
+            .unwrap(),
+            db_pool_wait: Histogram::with_opts(histogram(
+                "db_pool_wait_seconds",
+                "Time spent waiting for a database connection",
+            ))
+            .unwrap(),
+            db_pool_timeouts: IntCounter::new(
+                "db_pool_timeouts_total",
+                "Database connection checkout timeouts",
+            )
+            .unwrap(),
+            ws_connections: IntGauge::new("ws_connections", "Open WebSocket connections").unwrap(),
+            sse_connections: IntGauge::new("sse_connections", "Open Server-Sent Events connections").unwrap(),
+            ws_lagged_messages: IntCounter::new(
+                "ws_lagged_messages_total",
+                "Broadcast messages dropped for lagging WebSocket clients",
+            )
+            .unwrap(),
+            static_requests: IntCounterVec::new(
+                Opts::new("static_requests_total", "Static file requests"),
+                &["prefix", "status"],
+            )
+            .unwrap(),
+            registry,
+        };
+
+        let collectors: Vec<Box<dyn prometheus::core::Collector>> = vec![
+            Box::new(metrics.script_requests.clone()),
+            Box::new(metrics.script_duration.clone()),
+            Box::new(metrics.rpc_requests.clone()),
+            Box::new(metrics.rpc_duration.clone()),
+            Box::new(metrics.script_errors.clone()),
+            Box::new(metrics.script_timeouts.clone()),
+            Box::new(metrics.isolate_create.clone()),
+            Box::new(metrics.module_load.clone()),
+            Box::new(metrics.worker_pool.clone()),
+            Box::new(metrics.db_pool.clone()),
+            Box::new(metrics.db_pool_wait.clone()),
+            Box::new(metrics.db_pool_timeouts.clone()),
+            Box::new(metrics.ws_connections.clone()),
+            Box::new(metrics.sse_connections.clone()),
+            Box::new(metrics.ws_lagged_messages.clone()),
+            Box::new(metrics.static_requests.clone()),
+        ];
+        for collector in collectors {
+            metrics
+                .registry
+                .register(collector)
+                .expect("Failed to register metric");
+        }
+        metrics
+    }
+
+    /// 全局指标
+    pub fn global() -> &'static Metrics {
+        static METRICS: OnceLock<Metrics> = OnceLock::new();
+        METRICS.get_or_init(Self::new)
+    }
+
+    /// 记录一次脚本 HTTP 请求
+    pub fn observe_script(&self, path: &str, status: u16, elapsed: Duration) {
+        self.script_requests
+            .with_label_values(&[path, &status.to_string()])
+            .inc();
+        self.script_duration
+            .with_label_values(&[path])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// 记录一次 JSON-RPC 调用
+    pub fn observe_rpc(&self, method: &str, ok: bool, elapsed: Duration) {
+        let outcome = if ok { "success" } else { "error" };
+        self.rpc_requests.with_label_values(&[method, outcome]).inc();
+        self.rpc_duration
+            .with_label_values(&[method])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// 以 Prometheus 文本格式导出，抓取时刷新连接池和线程池的瞬时状态
+    pub fn render(&self, pool: &DbPool) -> String {
+        let state = pool.state();
+        let max = pool.max_size() as i64;
+        self.db_pool
+            .with_label_values(&["idle"])
+            .set(state.idle_connections as i64);
+        self.db_pool
+            .with_label_values(&["in_use"])
+            .set((state.connections - state.idle_connections) as i64);
+        self.db_pool.with_label_values(&["max"]).set(max);
+
+        let stats = WorkerPool::global().stats();
+        self.worker_pool
+            .with_label_values(&["active"])
+            .set(stats.active as i64);
+        self.worker_pool
+            .with_label_values(&["queued"])
+            .set(stats.queued as i64);
+
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("Failed to encode metrics");
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+/// r2d2 事件处理器 - 记录获取连接的等待时间和超时
+#[derive(Debug)]
+pub struct PoolEventMetrics;
+
+impl r2d2::HandleEvent for PoolEventMetrics {
+    fn handle_checkout(&self, event: r2d2::event::CheckoutEvent) {
+        Metrics::global()
+            .db_pool_wait
+            .observe(event.duration().as_secs_f64());
+    }
+
+    fn handle_timeout(&self, _event: r2d2::event::TimeoutEvent) {
+        Metrics::global().db_pool_timeouts.inc();
+    }
+}
+
+/// GET /metrics
+pub async fn metrics_handler(
+    State((pool, _)): State<(DbPool, Arc<crate::websocket::WebSocketState>)>,
+) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, TextEncoder::new().format_type().to_string())],
+        Metrics::global().render(&pool),
+    )
+}
+
+/// 静态资源计数中间件，按第一级路径统计以控制标签数量
+pub async fn track_static(req: Request, next: Next) -> Response {
+    let prefix = req
+        .uri()
+        .path()
+        .split('/')
+        .nth(1)
+        .filter(|s| !s.is_empty())
+        .map(|s| format!("/{}", s))
+        .unwrap_or_else(|| "/".to_string());
+    let response = next.run(req).await;
+    Metrics::global()
+        .static_requests
+        .with_label_values(&[prefix.as_str(), response.status().as_str()])
+        .inc();
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_contains_metrics() {
+        let metrics = Metrics::global();
+        metrics.observe_script("hello.js", 200, Duration::from_millis(5));
+        metrics.observe_rpc("add", true, Duration::from_millis(3));
+        metrics.script_timeouts.inc();
+
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&metrics.registry.gather(), &mut buffer)
+            .unwrap();
+        let text = String::from_utf8(buffer).unwrap();
+
+        assert!(text.contains(r#"ujs_script_requests_total{path="hello.js",status="200"}"#));
+        assert!(text.contains(r#"ujs_rpc_requests_total{method="add",outcome="success"}"#));
+        assert!(text.contains("ujs_script_request_duration_seconds_bucket"));
+        assert!(text.contains("ujs_script_timeouts_total"));
+    }
+}
@@ -0,0 +1,656 @@
+use crate::auth::AuthIdentity;
+use crate::js_bridge::jsonrpc::request_parser::{JsonRpcRequestType, RequestParser};
+use crate::js_bridge::models::{JsonRpcError, JsonRpcResponse};
+use crate::request_id::RequestId;
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use governor::clock::{Clock, DefaultClock};
+use governor::middleware::StateInformationMiddleware;
+use governor::state::keyed::DashMapStateStore;
+use governor::{InsufficientCapacity, Quota};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::num::NonZeroU32;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// 清理空闲客户端状态的间隔
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 按客户端分键的 governor 限流器，返回值中带剩余令牌数
+type KeyedLimiter = governor::RateLimiter<String, DashMapStateStore<String>, DefaultClock, StateInformationMiddleware>;
+
+/// 可信反向代理
+///
+/// 只有直接连接的对端属于可信代理时才读取 `X-Forwarded-For`：从右向左跳过可信代理，
+/// 第一个不可信的地址即客户端 IP；遇到无法解析的项时取最近一个可信跳点。未配置时忽略该请求头，直接使用对端地址。
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies(Vec<(IpAddr, u8)>);
+
+impl TrustedProxies {
+    /// 从 `10.0.0.1,192.168.0.0/16` 格式解析，无法解析的项忽略
+    pub fn parse(spec: &str) -> Self {
+        let networks = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .filter_map(|entry| {
+                let (addr, prefix) = entry.split_once('/').unwrap_or((entry, ""));
+                let addr: IpAddr = addr.parse().ok()?;
+                let max = if addr.is_ipv4() { 32 } else { 128 };
+                let prefix = if prefix.is_empty() { max } else { prefix.parse().ok()? };
+                (prefix <= max).then_some((addr, prefix))
+            })
+            .collect();
+        Self(networks)
+    }
+
+    /// 从环境变量 `TRUSTED_PROXIES` 读取
+    pub fn from_env() -> Self {
+        dotenvy::dotenv().ok();
+        Self::parse(&std::env::var("TRUSTED_PROXIES").unwrap_or_default())
+    }
+
+    /// 全局配置（单例模式），限流与 WebSocket 连接数限制共用
+    pub fn global() -> &'static TrustedProxies {
+        static PROXIES: OnceLock<TrustedProxies> = OnceLock::new();
+        PROXIES.get_or_init(Self::from_env)
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        self.0.iter().any(|&(network, prefix)| match (network, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        })
+    }
+
+    /// 解析客户端 IP，对端地址未知时返回 `None`
+    pub fn client_ip(&self, headers: &HeaderMap, peer: Option<IpAddr>) -> Option<IpAddr> {
+        let peer = peer?;
+        if !self.contains(peer) {
+            return Some(peer);
+        }
+        // 从右向左跳过可信代理；遇到无法解析的项时停止，使用最近一个可信跳点，
+        // 不再信任它左侧由客户端填写的地址
+        let mut last = peer;
+        let forwarded: Vec<&str> = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .collect();
+        for entry in forwarded.iter().rev() {
+            let Ok(ip) = entry.trim().parse::<IpAddr>() else {
+                break;
+            };
+            if !self.contains(ip) {
+                return Some(ip);
+            }
+            last = ip;
+        }
+        Some(last)
+    }
+}
+
+/// 限流维度
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RateLimitKey {
+    /// 按客户端 IP
+    Ip,
+    /// 按 `X-Api-Key`（或已认证身份），缺失时退化为 IP
+    ApiKey,
+}
+
+/// 令牌桶参数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limit {
+    /// 桶容量（允许的突发请求数）
+    pub burst: u32,
+    /// 每秒补充的令牌数
+    pub per_second: f64,
+}
+
+impl Limit {
+    pub fn new(burst: u32, per_second: f64) -> Self {
+        Self { burst, per_second }
+    }
+
+    /// 从 `burst:per_second` 格式解析
+    fn parse(spec: &str) -> Option<Self> {
+        let (burst, per_second) = spec.split_once(':')?;
+        Some(Self::new(burst.trim().parse().ok()?, per_second.trim().parse().ok()?))
+    }
+
+    /// 对应的 governor 配额，桶容量为 0 时返回 `None`
+    fn quota(self) -> Option<Quota> {
+        let burst = NonZeroU32::new(self.burst)?;
+        // 每秒补充 0 个令牌时取最长周期（近似不再补充）；周期 × 容量不能超出 governor 的纳秒计数范围
+        let max_period = Duration::from_nanos(u64::MAX / (self.burst as u64 + 1));
+        let period = Duration::try_from_secs_f64(1.0 / self.per_second)
+            .map_or(max_period, |period| period.clamp(Duration::from_nanos(1), max_period));
+        Quota::with_period(period).map(|quota| quota.allow_burst(burst))
+    }
+
+    /// 补充 `tokens` 个令牌需要的秒数
+    fn refill_secs(self, tokens: u32) -> u64 {
+        if self.per_second > 0.0 {
+            (tokens as f64 / self.per_second).ceil() as u64
+        } else {
+            u64::MAX
+        }
+    }
+}
+
+/// 限流配置
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    /// 是否启用限流
+    pub enabled: bool,
+    /// 客户端识别方式
+    pub key_by: RateLimitKey,
+    /// 默认限额（每个客户端共享）
+    pub default_limit: Limit,
+    /// 针对脚本路径（`/js/report.js`）或 JSON-RPC 方法（`rpc:add`）的独立限额
+    pub routes: HashMap<String, Limit>,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            key_by: RateLimitKey::Ip,
+            default_limit: Limit::new(60, 10.0),
+            routes: HashMap::new(),
+        }
+    }
+}
+
+impl RateLimitConfig {
+    /// 从环境变量读取配置
+    pub fn from_env() -> Self {
+        dotenvy::dotenv().ok();
+        let var = |key: &str| std::env::var(key).ok().filter(|v| !v.is_empty());
+        let defaults = Self::default();
+        let mut config = Self {
+            enabled: var("RATE_LIMIT_ENABLED").is_some_and(|v| v == "true" || v == "1"),
+            key_by: match var("RATE_LIMIT_KEY").as_deref() {
+                Some("api_key") => RateLimitKey::ApiKey,
+                _ => RateLimitKey::Ip,
+            },
+            default_limit: var("RATE_LIMIT_DEFAULT")
+                .and_then(|v| Limit::parse(&v))
+                .unwrap_or(defaults.default_limit),
+            routes: defaults.routes,
+        };
+        // 格式：/js/report.js=5:1,rpc:add=10:2
+        if let Some(routes) = var("RATE_LIMIT_ROUTES") {
+            for entry in routes.split(',') {
+                if let Some((route, limit)) = entry.trim().split_once('=')
+                    && let Some(limit) = Limit::parse(limit)
+                {
+                    config = config.with_route_limit(route, limit);
+                }
+            }
+        }
+        config
+    }
+
+    /// 设置某个路由的独立限额
+    pub fn with_route_limit(mut self, route: &str, limit: Limit) -> Self {
+        self.routes.insert(route.to_string(), limit);
+        self
+    }
+}
+
+/// 一个限额对应的令牌桶，每个客户端一个桶
+struct Bucket {
+    limit: Limit,
+    /// 桶容量为 0 时为 `None`，所有请求都被拒绝
+    limiter: Option<KeyedLimiter>,
+}
+
+impl Bucket {
+    fn new(limit: Limit) -> Self {
+        let limiter = limit
+            .quota()
+            .map(|quota| governor::RateLimiter::dashmap(quota).with_middleware::<StateInformationMiddleware>());
+        Self { limit, limiter }
+    }
+
+    /// 为客户端消耗 `count` 个令牌，令牌不足时一个都不扣
+    fn check(&self, client: &str, count: u32) -> RateLimitDecision {
+        let denied = |retry_after: u64| RateLimitDecision {
+            allowed: false,
+            limit: self.limit.burst,
+            remaining: 0,
+            retry_after: retry_after.max(1),
+        };
+        let Some(limiter) = &self.limiter else {
+            return denied(self.limit.refill_secs(count));
+        };
+        let count = NonZeroU32::new(count).unwrap_or(NonZeroU32::MIN);
+        match limiter.check_key_n(&client.to_string(), count) {
+            Ok(Ok(snapshot)) => RateLimitDecision {
+                allowed: true,
+                limit: self.limit.burst,
+                remaining: snapshot.remaining_burst_capacity(),
+                retry_after: 0,
+            },
+            Ok(Err(not_until)) => {
+                let wait = not_until.wait_time_from(limiter.clock().now());
+                denied(wait.as_secs_f64().ceil() as u64)
+            }
+            // 请求的令牌数超过桶容量，永远无法满足
+            Err(InsufficientCapacity(_)) => denied(self.limit.refill_secs(self.limit.burst)),
+        }
+    }
+
+    /// 清理已经补满令牌的客户端，它们与新客户端没有区别
+    fn retain_recent(&self) {
+        if let Some(limiter) = &self.limiter {
+            limiter.retain_recent();
+            limiter.shrink_to_fit();
+        }
+    }
+}
+
+/// 一次限流判断的结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimitDecision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    /// 被拒绝时需要等待的秒数
+    pub retry_after: u64,
+}
+
+impl RateLimitDecision {
+    /// 写入 X-RateLimit-* 响应头
+    fn apply_headers(&self, headers: &mut HeaderMap) {
+        headers.insert("x-ratelimit-limit", HeaderValue::from(self.limit));
+        headers.insert("x-ratelimit-remaining", HeaderValue::from(self.remaining));
+        if !self.allowed {
+            headers.insert(header::RETRY_AFTER, HeaderValue::from(self.retry_after));
+        }
+    }
+}
+
+/// 令牌桶限流器
+///
+/// 每个限额（默认限额和各路由的独立限额）对应一个 governor 的 keyed 限流器，以客户端为键；
+/// 令牌补满的客户端每隔 [`CLEANUP_INTERVAL`] 清理一次，内存占用只与最近活跃的客户端数量有关。
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    default_bucket: Bucket,
+    route_buckets: HashMap<String, Bucket>,
+    last_cleanup: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        let default_bucket = Bucket::new(config.default_limit);
+        let route_buckets = config
+            .routes
+            .iter()
+            .map(|(route, limit)| (route.clone(), Bucket::new(*limit)))
+            .collect();
+        Self {
+            config,
+            default_bucket,
+            route_buckets,
+            last_cleanup: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// 消耗一个令牌
+    ///
+    /// 路由有独立限额时使用该路由的桶，否则所有路由共享客户端的默认桶。
+    pub fn check(&self, client: &str, route: &str) -> RateLimitDecision {
+        self.check_all(client, &[route])
+    }
+
+    /// 每个路由消耗一个令牌，令牌不足时整批拒绝
+    ///
+    /// 批量 JSON-RPC 请求用它避免被拒绝时前面的方法白白消耗令牌：共用一个桶的路由一次扣除，
+    /// 不足时一个都不扣；涉及多个桶时先扣独立限额的桶，最后扣默认桶，governor 不支持退还令牌，
+    /// 因此只有默认桶不足时已扣除的独立限额令牌不退还。返回剩余令牌最少的桶的结果，
+    /// 被拒绝时返回令牌不足的桶的结果。
+    pub fn check_all(&self, client: &str, routes: &[&str]) -> RateLimitDecision {
+        self.cleanup();
+
+        // 同一个桶可能被多个路由共用，先按桶汇总需要的令牌数
+        let mut needed: Vec<(&Bucket, u32)> = Vec::new();
+        let mut default_count = 0;
+        for route in routes {
+            match self.route_buckets.get(*route) {
+                Some(bucket) => match needed.iter_mut().find(|(b, _)| std::ptr::eq(*b, bucket)) {
+                    Some((_, count)) => *count += 1,
+                    None => needed.push((bucket, 1)),
+                },
+                None => default_count += 1,
+            }
+        }
+        if default_count > 0 {
+            needed.push((&self.default_bucket, default_count));
+        }
+
+        let mut decision: Option<RateLimitDecision> = None;
+        for (bucket, count) in needed {
+            let result = bucket.check(client, count);
+            if !result.allowed {
+                return result;
+            }
+            if decision.as_ref().is_none_or(|d| result.remaining < d.remaining) {
+                decision = Some(result);
+            }
+        }
+        decision.unwrap_or(RateLimitDecision {
+            allowed: true,
+            limit: self.config.default_limit.burst,
+            remaining: self.config.default_limit.burst,
+            retry_after: 0,
+        })
+    }
+
+    /// 距上次清理超过 [`CLEANUP_INTERVAL`] 时清理令牌已补满的客户端
+    fn cleanup(&self) {
+        let Ok(mut last) = self.last_cleanup.try_lock() else {
+            return;
+        };
+        if last.elapsed() < CLEANUP_INTERVAL {
+            return;
+        }
+        *last = Instant::now();
+        drop(last);
+        self.retain_recent();
+    }
+
+    fn retain_recent(&self) {
+        self.default_bucket.retain_recent();
+        self.route_buckets.values().for_each(Bucket::retain_recent);
+    }
+
+    /// 识别客户端
+    ///
+    /// `api_key` 模式只使用认证中间件校验过的身份，未认证的请求（包括未经校验的 `X-Api-Key`）按 IP 计数。
+    pub fn client_key(&self, headers: &HeaderMap, peer: Option<IpAddr>, user: Option<&AuthIdentity>) -> String {
+        if self.config.key_by == RateLimitKey::ApiKey
+            && let Some(user) = user
+        {
+            return format!("user:{}", user.subject);
+        }
+        match TrustedProxies::global().client_ip(headers, peer) {
+            Some(ip) => format!("ip:{}", ip),
+            None => "ip:unknown".to_string(),
+        }
+    }
+}
+
+/// 请求对应的限流器和客户端，由限流中间件写入请求扩展
+///
+/// WebSocket 连接升级后中间件看不到后续消息，JSON-RPC 模式的连接用它对每条调用计数。
+#[derive(Clone)]
+pub struct RateLimitClient {
+    limiter: Arc<RateLimiter>,
+    key: String,
+}
+
+impl RateLimitClient {
+    pub fn new(limiter: Arc<RateLimiter>, key: String) -> Self {
+        Self { limiter, key }
+    }
+
+    /// 为 JSON-RPC 方法消耗一个令牌
+    pub fn check_rpc(&self, method: &str) -> RateLimitDecision {
+        let decision = self.limiter.check(&self.key, &rpc_route(method));
+        if !decision.allowed {
+            tracing::warn!("rate limit exceeded: {} rpc:{}", self.key, method);
+        }
+        decision
+    }
+}
+
+/// 限流中间件
+///
+/// `/rpc` 请求按 JSON-RPC 方法（`rpc:<method>`）计数，批量请求中每个方法各消耗一个令牌，
+/// 超限时返回 JSON-RPC 错误；其他路由按请求路径计数，超限时返回 429。
+/// 请求扩展中写入 [`RateLimitClient`]，供 WebSocket 连接对后续消息计数。
+pub async fn rate_limit(
+    State(limiter): State<Arc<RateLimiter>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if !limiter.is_enabled() {
+        return next.run(req).await;
+    }
+
+    let peer = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    let client = limiter.client_key(req.headers(), peer, req.extensions().get::<AuthIdentity>());
+
+    if req.uri().path() == "/rpc" {
+        let (parts, body) = req.into_parts();
+        let bytes = match axum::body::to_bytes(body, 1024 * 1024).await {
+            Ok(b) => b,
+            Err(_) => return StatusCode::PAYLOAD_TOO_LARGE.into_response(),
+        };
+
+        let methods = rpc_methods(&bytes);
+        let routes: Vec<String> = if methods.is_empty() {
+            vec!["/rpc".to_string()]
+        } else {
+            methods.iter().map(|m| rpc_route(m)).collect()
+        };
+        let routes: Vec<&str> = routes.iter().map(String::as_str).collect();
+
+        let decision = limiter.check_all(&client, &routes);
+        if !decision.allowed {
+            tracing::warn!("rate limit exceeded: {} {:?}", client, routes);
+            let request_id = parts.extensions.get::<RequestId>().map(RequestId::as_str);
+            let error = JsonRpcError::rate_limited(decision.retry_after).with_request_id(request_id);
+            let mut response = JsonRpcResponse::error(error, Some(serde_json::Value::Null)).into_response();
+            decision.apply_headers(response.headers_mut());
+            return response;
+        }
+
+        let mut response = next.run(Request::from_parts(parts, Body::from(bytes))).await;
+        decision.apply_headers(response.headers_mut());
+        return response;
+    }
+
+    let decision = limiter.check(&client, req.uri().path());
+    if !decision.allowed {
+        tracing::warn!("rate limit exceeded: {} {}", client, req.uri().path());
+        let mut response = (StatusCode::TOO_MANY_REQUESTS, "Too Many Requests").into_response();
+        decision.apply_headers(response.headers_mut());
+        return response;
+    }
+
+    let mut req = req;
+    req.extensions_mut().insert(RateLimitClient::new(limiter.clone(), client));
+    let mut response = next.run(req).await;
+    decision.apply_headers(response.headers_mut());
+    response
+}
+
+/// JSON-RPC 方法对应的限流路由
+pub fn rpc_route(method: &str) -> String {
+    format!("rpc:{}", method)
+}
+
+/// 从 JSON-RPC 请求体中提取方法名（单个或批量），批量请求中不合法的元素不计入
+fn rpc_methods(body: &[u8]) -> Vec<String> {
+    match RequestParser::parse_json_rpc_request(&String::from_utf8_lossy(body)) {
+        Ok(JsonRpcRequestType::Single(req)) => vec![req.method],
+        Ok(JsonRpcRequestType::Batch(reqs)) => reqs.into_iter().flatten().map(|r| r.method).collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(burst: u32, per_second: f64) -> RateLimiter {
+        RateLimiter::new(RateLimitConfig {
+            enabled: true,
+            default_limit: Limit::new(burst, per_second),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_token_bucket_exhaustion() {
+        let limiter = limiter(2, 0.5);
+
+        let first = limiter.check("ip:1.1.1.1", "/js/a.js");
+        assert!(first.allowed);
+        assert_eq!(first.remaining, 1);
+        assert!(limiter.check("ip:1.1.1.1", "/js/b.js").allowed);
+
+        let denied = limiter.check("ip:1.1.1.1", "/js/a.js");
+        assert!(!denied.allowed);
+        assert_eq!(denied.retry_after, 2);
+
+        // 其他客户端不受影响
+        assert!(limiter.check("ip:2.2.2.2", "/js/a.js").allowed);
+    }
+
+    #[test]
+    fn test_route_specific_limit() {
+        let config = RateLimitConfig {
+            enabled: true,
+            ..Default::default()
+        }
+        .with_route_limit("rpc:report", Limit::new(1, 0.1));
+        let limiter = RateLimiter::new(config);
+
+        assert!(limiter.check("ip:1.1.1.1", "rpc:report").allowed);
+        assert!(!limiter.check("ip:1.1.1.1", "rpc:report").allowed);
+        // 默认桶独立计数
+        assert!(limiter.check("ip:1.1.1.1", "rpc:add").allowed);
+    }
+
+    #[test]
+    fn test_batch_is_all_or_nothing() {
+        let config = RateLimitConfig {
+            enabled: true,
+            ..Default::default()
+        }
+        .with_route_limit("rpc:report", Limit::new(1, 0.001));
+        let routed = RateLimiter::new(config);
+        assert!(routed.check("ip:1.1.1.1", "rpc:report").allowed);
+
+        // report 没有令牌时整批拒绝，add 的令牌不被消耗
+        assert!(!routed.check_all("ip:1.1.1.1", &["rpc:add", "rpc:add", "rpc:report"]).allowed);
+        let fresh = routed.check("ip:2.2.2.2", "rpc:add").remaining;
+        assert_eq!(routed.check("ip:1.1.1.1", "rpc:add").remaining, fresh);
+
+        // 多个方法共用一个桶时按总数检查
+        let shared = limiter(2, 0.001);
+        assert!(!shared.check_all("ip:1.1.1.1", &["rpc:a", "rpc:b", "rpc:c"]).allowed);
+        assert_eq!(shared.check_all("ip:1.1.1.1", &["rpc:a", "rpc:b"]).remaining, 0);
+    }
+
+    #[test]
+    fn test_idle_clients_are_cleaned_up() {
+        let limiter = limiter(1, 1000.0);
+        for i in 0..100 {
+            limiter.check(&format!("ip:{}", i), "/js/a.js");
+        }
+        let keys = || limiter.default_bucket.limiter.as_ref().unwrap().len();
+        assert_eq!(keys(), 100);
+
+        // 令牌补满后与新客户端没有区别，可以清理
+        std::thread::sleep(Duration::from_millis(20));
+        limiter.retain_recent();
+        assert_eq!(keys(), 0);
+    }
+
+    #[test]
+    fn test_zero_limits() {
+        let empty = limiter(0, 1.0);
+        let denied = empty.check("ip:1.1.1.1", "/js/a.js");
+        assert!(!denied.allowed);
+        assert_eq!(denied.retry_after, 1);
+
+        // 不补充令牌时只能用完初始容量
+        let no_refill = limiter(1, 0.0);
+        assert!(no_refill.check("ip:1.1.1.1", "/js/a.js").allowed);
+        assert!(!no_refill.check("ip:1.1.1.1", "/js/a.js").allowed);
+    }
+
+    #[test]
+    fn test_trusted_proxies() {
+        let proxies = TrustedProxies::parse("10.0.0.0/8, 192.168.1.1, bogus");
+        let peer: IpAddr = "10.1.2.3".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", HeaderValue::from_static("1.1.1.1, 2.2.2.2, 192.168.1.1"));
+
+        // 跳过可信代理，取最右侧的不可信地址
+        assert_eq!(proxies.client_ip(&headers, Some(peer)), Some("2.2.2.2".parse().unwrap()));
+        // 对端不是可信代理时忽略 X-Forwarded-For
+        let direct: IpAddr = "8.8.8.8".parse().unwrap();
+        assert_eq!(proxies.client_ip(&headers, Some(direct)), Some(direct));
+        assert_eq!(TrustedProxies::default().client_ip(&headers, Some(peer)), Some(peer));
+
+        // 无法解析的项之后（左侧）的地址由客户端伪造，不予采用
+        headers.insert("x-forwarded-for", HeaderValue::from_static("6.6.6.6, garbage"));
+        assert_eq!(proxies.client_ip(&headers, Some(peer)), Some(peer));
+        headers.insert("x-forwarded-for", HeaderValue::from_static("6.6.6.6, garbage, 192.168.1.1"));
+        assert_eq!(proxies.client_ip(&headers, Some(peer)), Some("192.168.1.1".parse().unwrap()));
+        // 全部是可信代理时取最左侧的可信跳点
+        headers.insert("x-forwarded-for", HeaderValue::from_static("10.9.9.9, 192.168.1.1"));
+        assert_eq!(proxies.client_ip(&headers, Some(peer)), Some("10.9.9.9".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_client_key_ignores_unverified_api_key() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            enabled: true,
+            key_by: RateLimitKey::ApiKey,
+            ..Default::default()
+        });
+        let mut headers = HeaderMap::new();
+        headers.insert("x-api-key", HeaderValue::from_static("anything"));
+        let peer = Some("8.8.8.8".parse().unwrap());
+        assert_eq!(limiter.client_key(&headers, peer, None), "ip:8.8.8.8");
+        let user = AuthIdentity::new("alice", "api_key", serde_json::Value::Null);
+        assert_eq!(limiter.client_key(&headers, peer, Some(&user)), "user:alice");
+    }
+
+    #[test]
+    fn test_limit_parse() {
+        assert_eq!(Limit::parse("5:0.5"), Some(Limit::new(5, 0.5)));
+        assert_eq!(Limit::parse("5"), None);
+    }
+
+    #[test]
+    fn test_rpc_methods() {
+        assert_eq!(rpc_methods(br#"{"jsonrpc":"2.0","method":"add","id":1}"#), vec!["add"]);
+        assert_eq!(
+            rpc_methods(br#"[{"jsonrpc":"2.0","method":"a"},{"jsonrpc":"2.0","method":"b"}]"#),
+            vec!["a", "b"]
+        );
+        assert_eq!(rpc_methods(br#"[1,{"jsonrpc":"2.0","method":"b","id":2}]"#), vec!["b"]);
+        assert!(rpc_methods(b"nope").is_empty());
+    }
+}
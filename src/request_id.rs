@@ -0,0 +1,93 @@
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+
+/// 请求 ID 头
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// 客户端传入的请求 ID 最大长度，超出或含非法字符时重新生成
+const MAX_LEN: usize = 128;
+
+/// 一次 HTTP 请求的关联 ID，写入请求扩展
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    /// 生成 32 位十六进制随机 ID
+    pub fn generate() -> Self {
+        Self(hex::encode(rand::random::<[u8; 16]>()))
+    }
+
+    /// 校验客户端传入的 ID（可见 ASCII，且不超过 128 字符）
+    fn from_header(value: &HeaderValue) -> Option<Self> {
+        let value = value.to_str().ok()?.trim();
+        let valid = !value.is_empty()
+            && value.len() <= MAX_LEN
+            && value.bytes().all(|b| b.is_ascii_graphic());
+        valid.then(|| Self(value.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// 请求 ID 中间件
+///
+/// 沿用客户端的 `X-Request-Id` 或生成新的 ID，写回请求头（脚本通过 `request.id` 读取）
+/// 和请求扩展，并在响应头中原样返回。
+pub async fn propagate(mut req: Request, next: Next) -> Response {
+    let id = req
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(RequestId::from_header)
+        .unwrap_or_else(RequestId::generate);
+
+    let value = HeaderValue::from_str(id.as_str()).expect("request id is visible ASCII");
+    req.headers_mut().insert(REQUEST_ID_HEADER, value.clone());
+    req.extensions_mut().insert(id);
+
+    let mut response = next.run(req).await;
+    response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::Body, middleware, routing::get};
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route(
+                "/",
+                get(|req: Request| async move {
+                    req.extensions().get::<RequestId>().unwrap().0.clone()
+                }),
+            )
+            .layer(middleware::from_fn(propagate))
+    }
+
+    #[tokio::test]
+    async fn test_request_id_is_echoed() {
+        let req = Request::get("/").header("x-request-id", "abc-123").body(Body::empty()).unwrap();
+        let response = app().oneshot(req).await.unwrap();
+        assert_eq!(response.headers()["x-request-id"], "abc-123");
+
+        let body = axum::body::to_bytes(response.into_body(), 1024).await.unwrap();
+        assert_eq!(&body[..], b"abc-123");
+    }
+
+    #[tokio::test]
+    async fn test_request_id_is_generated() {
+        let long = "x".repeat(MAX_LEN + 1);
+        let req = Request::get("/").header("x-request-id", long).body(Body::empty()).unwrap();
+        let response = app().oneshot(req).await.unwrap();
+        let id = response.headers()["x-request-id"].to_str().unwrap();
+        assert_eq!(id.len(), 32);
+    }
+}
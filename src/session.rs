@@ -0,0 +1,307 @@
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::Sha256;
+use std::sync::{Arc, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 会话配置
+#[derive(Debug, Clone)]
+pub struct SessionConfig {
+    /// Cookie 名称
+    pub cookie_name: String,
+    /// 签名密钥
+    pub secret: Vec<u8>,
+    /// 会话有效期（秒）
+    pub max_age: u64,
+    /// Cookie Path 属性
+    pub path: String,
+    /// 是否设置 Secure 属性
+    pub secure: bool,
+    /// 是否设置 HttpOnly 属性
+    pub http_only: bool,
+    /// SameSite 属性（Strict / Lax / None）
+    pub same_site: String,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            cookie_name: "ujs_session".to_string(),
+            secret: rand::random::<[u8; 32]>().to_vec(),
+            max_age: 86400, // 默认 1 天
+            path: "/".to_string(),
+            secure: true,
+            http_only: true,
+            same_site: "Lax".to_string(),
+        }
+    }
+}
+
+impl SessionConfig {
+    /// 创建新的会话配置（随机密钥）
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 从环境变量读取配置
+    ///
+    /// 未设置 `SESSION_SECRET` 时使用进程级随机密钥，重启后已有会话全部失效。
+    pub fn from_env() -> Self {
+        dotenvy::dotenv().ok();
+        let mut config = Self::new();
+
+        match std::env::var("SESSION_SECRET") {
+            Ok(secret) if !secret.is_empty() => config = config.with_secret(secret.as_bytes()),
+            _ => tracing::warn!("SESSION_SECRET 未设置，使用随机会话密钥"),
+        }
+        if let Ok(name) = std::env::var("SESSION_COOKIE_NAME") {
+            config.cookie_name = name;
+        }
+        if let Some(max_age) = env_parse("SESSION_MAX_AGE") {
+            config.max_age = max_age;
+        }
+        if let Some(secure) = env_parse("SESSION_COOKIE_SECURE") {
+            config.secure = secure;
+        }
+        if let Ok(same_site) = std::env::var("SESSION_COOKIE_SAMESITE") {
+            config.same_site = same_site;
+        }
+        config
+    }
+
+    /// 全局会话配置（首次访问时从环境变量初始化）
+    pub fn global() -> Arc<SessionConfig> {
+        static CONFIG: OnceLock<Arc<SessionConfig>> = OnceLock::new();
+        CONFIG.get_or_init(|| Arc::new(Self::from_env())).clone()
+    }
+
+    /// 设置签名密钥
+    pub fn with_secret(mut self, secret: &[u8]) -> Self {
+        self.secret = secret.to_vec();
+        self
+    }
+
+    fn sign(&self, payload: &str) -> String {
+        let mut mac = HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts any key length");
+        mac.update(payload.as_bytes());
+        URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes())
+    }
+
+    fn verify(&self, payload: &str, signature: &str) -> bool {
+        let Ok(signature) = URL_SAFE_NO_PAD.decode(signature) else {
+            return false;
+        };
+        let mut mac = HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts any key length");
+        mac.update(payload.as_bytes());
+        mac.verify_slice(&signature).is_ok()
+    }
+
+    /// 构建 Set-Cookie 头的值
+    fn build_cookie(&self, value: &str, max_age: u64) -> String {
+        let mut cookie = format!(
+            "{}={}; Path={}; Max-Age={}; SameSite={}",
+            self.cookie_name, value, self.path, max_age, self.same_site
+        );
+        if self.http_only {
+            cookie.push_str("; HttpOnly");
+        }
+        if self.secure {
+            cookie.push_str("; Secure");
+        }
+        cookie
+    }
+}
+
+/// 签名 Cookie 中保存的会话内容
+#[derive(Serialize, Deserialize)]
+struct SessionPayload {
+    data: Map<String, Value>,
+    exp: u64,
+}
+
+/// 会话 - 数据保存在 HMAC-SHA256 签名的 Cookie 中
+#[derive(Debug, Clone)]
+pub struct Session {
+    config: Arc<SessionConfig>,
+    data: Map<String, Value>,
+    modified: bool,
+    destroyed: bool,
+}
+
+impl Session {
+    /// 创建空会话
+    pub fn new(config: Arc<SessionConfig>) -> Self {
+        Self {
+            config,
+            data: Map::new(),
+            modified: false,
+            destroyed: false,
+        }
+    }
+
+    /// 从请求的 Cookie 头加载会话，签名无效或已过期时返回空会话
+    pub fn load(config: Arc<SessionConfig>, cookie_header: Option<&str>) -> Self {
+        let data = cookie_header
+            .and_then(|header| parse_cookie(header, &config.cookie_name))
+            .and_then(|value| Self::decode(&config, &value))
+            .unwrap_or_default();
+
+        let mut session = Self::new(config);
+        session.data = data;
+        session
+    }
+
+    fn decode(config: &SessionConfig, value: &str) -> Option<Map<String, Value>> {
+        let (payload, signature) = value.split_once('.')?;
+        if !config.verify(payload, signature) {
+            return None;
+        }
+        let bytes = URL_SAFE_NO_PAD.decode(payload).ok()?;
+        let payload: SessionPayload = serde_json::from_slice(&bytes).ok()?;
+        if payload.exp < now_secs() {
+            return None;
+        }
+        Some(payload.data)
+    }
+
+    fn encode(&self) -> String {
+        let payload = SessionPayload {
+            data: self.data.clone(),
+            exp: now_secs() + self.config.max_age,
+        };
+        let json = serde_json::to_vec(&payload).unwrap_or_default();
+        let payload = URL_SAFE_NO_PAD.encode(json);
+        let signature = self.config.sign(&payload);
+        format!("{}.{}", payload, signature)
+    }
+
+    pub fn get(&self, key: &str) -> Option<Value> {
+        self.data.get(key).cloned()
+    }
+
+    pub fn set(&mut self, key: String, value: Value) {
+        self.data.insert(key, value);
+        self.modified = true;
+        self.destroyed = false;
+    }
+
+    pub fn remove(&mut self, key: &str) {
+        if self.data.remove(key).is_some() {
+            self.modified = true;
+        }
+    }
+
+    pub fn all(&self) -> Map<String, Value> {
+        self.data.clone()
+    }
+
+    /// 销毁会话，响应中会下发过期的 Cookie
+    pub fn destroy(&mut self) {
+        self.data.clear();
+        self.destroyed = true;
+        self.modified = false;
+    }
+
+    /// 会话有变化时返回需要下发的 Set-Cookie 值
+    pub fn set_cookie_header(&self) -> Option<String> {
+        if self.destroyed {
+            Some(self.config.build_cookie("", 0))
+        } else if self.modified {
+            Some(self.config.build_cookie(&self.encode(), self.config.max_age))
+        } else {
+            None
+        }
+    }
+}
+
+/// 从 Cookie 头中取出指定名称的值
+pub fn parse_cookie(header: &str, name: &str) -> Option<String> {
+    header.split(';').find_map(|pair| {
+        let (k, v) = pair.trim().split_once('=')?;
+        (k == name).then(|| v.to_string())
+    })
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn env_parse<T: std::str::FromStr>(key: &str) -> Option<T> {
+    std::env::var(key).ok().and_then(|v| v.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn test_config() -> Arc<SessionConfig> {
+        Arc::new(SessionConfig::new().with_secret(b"test-secret"))
+    }
+
+    fn cookie_value(set_cookie: &str) -> String {
+        let first = set_cookie.split(';').next().unwrap();
+        first.split_once('=').unwrap().1.to_string()
+    }
+
+    #[test]
+    fn test_session_roundtrip() {
+        let config = test_config();
+        let mut session = Session::new(config.clone());
+        session.set("user_id".to_string(), json!(42));
+
+        let set_cookie = session.set_cookie_header().unwrap();
+        assert!(set_cookie.starts_with("ujs_session="));
+        assert!(set_cookie.contains("HttpOnly"));
+        assert!(set_cookie.contains("Secure"));
+        assert!(set_cookie.contains("SameSite=Lax"));
+
+        let header = format!("other=1; ujs_session={}", cookie_value(&set_cookie));
+        let loaded = Session::load(config, Some(&header));
+        assert_eq!(loaded.get("user_id"), Some(json!(42)));
+        assert!(loaded.set_cookie_header().is_none());
+    }
+
+    #[test]
+    fn test_session_rejects_tampered_cookie() {
+        let config = test_config();
+        let mut session = Session::new(config.clone());
+        session.set("role".to_string(), json!("user"));
+        let value = cookie_value(&session.set_cookie_header().unwrap());
+
+        let other = Arc::new(SessionConfig::new().with_secret(b"other-secret"));
+        let loaded = Session::load(other, Some(&format!("ujs_session={}", value)));
+        assert!(loaded.get("role").is_none());
+
+        let (_, signature) = value.split_once('.').unwrap();
+        let forged = URL_SAFE_NO_PAD.encode(r#"{"data":{"role":"admin"},"exp":99999999999}"#);
+        let header = format!("ujs_session={}.{}", forged, signature);
+        let loaded = Session::load(config, Some(&header));
+        assert!(loaded.get("role").is_none());
+    }
+
+    #[test]
+    fn test_session_destroy_expires_cookie() {
+        let mut session = Session::new(test_config());
+        session.set("k".to_string(), json!("v"));
+        session.destroy();
+
+        let set_cookie = session.set_cookie_header().unwrap();
+        assert!(set_cookie.starts_with("ujs_session=;"));
+        assert!(set_cookie.contains("Max-Age=0"));
+    }
+
+    #[test]
+    fn test_parse_cookie() {
+        assert_eq!(parse_cookie("a=1; b=2", "b"), Some("2".to_string()));
+        assert_eq!(parse_cookie("a=1", "b"), None);
+    }
+}
@@ -0,0 +1,330 @@
+use axum::http::{HeaderMap, Request};
+use opentelemetry::propagation::Extractor;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::{Context, global};
+use opentelemetry_otlp::{Protocol, WithExportConfig};
+use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+use opentelemetry_proto::transform::common::tonic::ResourceAttributesWithSchema;
+use opentelemetry_proto::transform::trace::tonic::group_spans_by_resource_and_scope;
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::error::{OTelSdkError, OTelSdkResult};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider, SpanData, SpanExporter};
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// 链路追踪导出方式
+#[derive(Debug, Clone, PartialEq)]
+pub enum TraceExporterKind {
+    /// 不导出（默认）
+    None,
+    /// 以 OTLP JSON 行输出到标准输出，便于本地调试
+    Stdout,
+    /// 以 OTLP JSON 行追加写入文件
+    File(String),
+    /// 通过 OTLP/HTTP 发送到 Collector / Jaeger，地址、请求头和超时由 `OTEL_EXPORTER_OTLP_*` 环境变量决定
+    Otlp(Protocol),
+}
+
+/// 链路追踪配置
+#[derive(Debug, Clone)]
+pub struct TelemetryConfig {
+    pub exporter: TraceExporterKind,
+    /// 上报的 `service.name`
+    pub service_name: String,
+    /// 根 Span 采样比例（0.0 ~ 1.0），已采样的上游请求始终跟随
+    pub sample_ratio: f64,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            exporter: TraceExporterKind::None,
+            service_name: "ujs-web-svr".to_string(),
+            sample_ratio: 1.0,
+        }
+    }
+}
+
+impl TelemetryConfig {
+    /// 从环境变量读取配置
+    pub fn from_env() -> Result<Self, String> {
+        dotenvy::dotenv().ok();
+        let var = |key: &str| std::env::var(key).ok().filter(|v| !v.is_empty());
+        let defaults = Self::default();
+
+        let exporter = match var("OTEL_TRACES_EXPORTER").as_deref() {
+            None | Some("none") => TraceExporterKind::None,
+            Some("stdout") => TraceExporterKind::Stdout,
+            Some("file") => TraceExporterKind::File(
+                var("OTEL_TRACES_FILE").unwrap_or_else(|| "logs/traces.jsonl".to_string()),
+            ),
+            Some("otlp") | Some("jaeger") => TraceExporterKind::Otlp(parse_protocol(
+                var("OTEL_EXPORTER_OTLP_PROTOCOL").as_deref(),
+            )?),
+            Some(other) => return Err(format!("Unknown OTEL_TRACES_EXPORTER: {}", other)),
+        };
+
+        Ok(Self {
+            exporter,
+            service_name: var("OTEL_SERVICE_NAME").unwrap_or(defaults.service_name),
+            sample_ratio: var("OTEL_TRACES_SAMPLER_ARG")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.sample_ratio),
+        })
+    }
+
+    /// 创建 TracerProvider 并注册 W3C Trace Context 传播器，未启用导出时返回 None
+    pub fn build_provider(&self) -> Result<Option<SdkTracerProvider>, String> {
+        let builder = SdkTracerProvider::builder()
+            .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+                self.sample_ratio,
+            ))))
+            .with_resource(
+                Resource::builder()
+                    .with_service_name(self.service_name.clone())
+                    .build(),
+            );
+
+        let provider = match &self.exporter {
+            TraceExporterKind::None => return Ok(None),
+            TraceExporterKind::Stdout => builder
+                .with_batch_exporter(JsonLinesExporter::new(Box::new(std::io::stdout())))
+                .build(),
+            TraceExporterKind::File(path) => {
+                if let Some(dir) = std::path::Path::new(path).parent() {
+                    let _ = std::fs::create_dir_all(dir);
+                }
+                let file = std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .map_err(|e| format!("Failed to open trace file {}: {}", path, e))?;
+                builder
+                    .with_batch_exporter(JsonLinesExporter::new(Box::new(file)))
+                    .build()
+            }
+            TraceExporterKind::Otlp(protocol) => {
+                let exporter = opentelemetry_otlp::SpanExporter::builder()
+                    .with_http()
+                    .with_protocol(*protocol)
+                    .build()
+                    .map_err(|e| format!("Failed to create OTLP exporter: {}", e))?;
+                builder.with_batch_exporter(exporter).build()
+            }
+        };
+
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        Ok(Some(provider))
+    }
+}
+
+/// 解析 `OTEL_EXPORTER_OTLP_PROTOCOL`，默认 `http/protobuf`
+fn parse_protocol(protocol: Option<&str>) -> Result<Protocol, String> {
+    match protocol {
+        None | Some("http/protobuf") => Ok(Protocol::HttpBinary),
+        Some("http/json") => Ok(Protocol::HttpJson),
+        Some(other) => Err(format!("Unsupported OTEL_EXPORTER_OTLP_PROTOCOL: {}", other)),
+    }
+}
+
+/// Span 中记录的 SQL 语句
+///
+/// SQL 可能包含字面量形式的敏感数据，只有设置 `OTEL_DB_STATEMENT=true` 时才记录。
+pub fn db_statement(sql: &str) -> Option<&str> {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    let enabled = *ENABLED.get_or_init(|| {
+        dotenvy::dotenv().ok();
+        std::env::var("OTEL_DB_STATEMENT").is_ok_and(|v| v == "true" || v == "1")
+    });
+    enabled.then_some(sql)
+}
+
+/// 创建 `tracing` 与 OpenTelemetry 的桥接层
+pub fn layer<S>(
+    provider: &SdkTracerProvider,
+) -> tracing_opentelemetry::OpenTelemetryLayer<S, opentelemetry_sdk::trace::Tracer>
+where
+    S: tracing::Subscriber + for<'span> tracing_subscriber::registry::LookupSpan<'span>,
+{
+    tracing_opentelemetry::layer().with_tracer(provider.tracer("ujs-web-svr"))
+}
+
+/// 为 HTTP 请求创建根 Span，并以请求头中的 `traceparent` 作为远端父 Span
+pub fn make_http_span<B>(req: &Request<B>) -> tracing::Span {
+    let span = tracing::info_span!(
+        "http_request",
+        otel.name = %format!("{} {}", req.method(), req.uri().path()),
+        otel.kind = "server",
+        http.method = %req.method(),
+        http.target = %req.uri(),
+        request_id = req
+            .headers()
+            .get(&crate::request_id::REQUEST_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or(""),
+    );
+    let _ = span.set_parent(extract_context(req.headers()));
+    span
+}
+
+/// 从请求头中提取上游的追踪上下文
+pub fn extract_context(headers: &HeaderMap) -> Context {
+    global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(headers)))
+}
+
+/// 当前 Span 的 W3C `traceparent`，未启用追踪时为 None
+pub fn current_traceparent() -> Option<String> {
+    let cx = tracing::Span::current().context();
+    let mut carrier: HashMap<String, String> = HashMap::new();
+    global::get_text_map_propagator(|propagator| propagator.inject_context(&cx, &mut carrier));
+    carrier.remove("traceparent")
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|v| v.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|k| k.as_str()).collect()
+    }
+}
+
+/// 以 OTLP JSON 行写入 stdout 或文件的导出器（每批一行，与 Collector 的 file exporter 格式一致）
+///
+/// 编码使用 `opentelemetry-proto` 的 OTLP 消息类型及其 serde 实现，与 OTLP/HTTP JSON 导出一致。
+#[derive(Debug)]
+struct JsonLinesExporter {
+    writer: Mutex<WriterBox>,
+    resource: ResourceAttributesWithSchema,
+}
+
+struct WriterBox(Box<dyn Write + Send>);
+
+impl std::fmt::Debug for WriterBox {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("WriterBox")
+    }
+}
+
+impl JsonLinesExporter {
+    fn new(writer: Box<dyn Write + Send>) -> Self {
+        Self {
+            writer: Mutex::new(WriterBox(writer)),
+            resource: ResourceAttributesWithSchema::default(),
+        }
+    }
+}
+
+impl SpanExporter for JsonLinesExporter {
+    async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        let request = ExportTraceServiceRequest {
+            resource_spans: group_spans_by_resource_and_scope(batch, &self.resource),
+        };
+        let line = serde_json::to_string(&request).map_err(|e| OTelSdkError::InternalFailure(e.to_string()))?;
+        let mut writer = self
+            .writer
+            .lock()
+            .map_err(|_| OTelSdkError::InternalFailure("trace writer poisoned".to_string()))?;
+        writeln!(writer.0, "{}", line)
+            .and_then(|_| writer.0.flush())
+            .map_err(|e| OTelSdkError::InternalFailure(e.to_string()))
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.resource = resource.into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::KeyValue;
+    use opentelemetry::trace::{SpanContext, SpanId, SpanKind, Status, TraceFlags, TraceId, TraceState};
+    use opentelemetry_sdk::trace::{SpanEvents, SpanLinks};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_parse_protocol() {
+        assert_eq!(parse_protocol(None).unwrap(), Protocol::HttpBinary);
+        assert_eq!(parse_protocol(Some("http/json")).unwrap(), Protocol::HttpJson);
+        assert!(parse_protocol(Some("grpc")).is_err());
+    }
+
+    #[test]
+    fn test_extract_traceparent() {
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "traceparent",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01".parse().unwrap(),
+        );
+
+        let cx = extract_context(&headers);
+        let span = opentelemetry::trace::TraceContextExt::span(&cx);
+        let span_context = span.span_context();
+        assert!(span_context.is_remote());
+        assert_eq!(
+            span_context.trace_id().to_string(),
+            "4bf92f3577b34da6a3ce929d0e0e4736"
+        );
+    }
+
+    #[derive(Clone, Default)]
+    struct Buffer(std::sync::Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_json_lines_exporter() {
+        let span = SpanData {
+            span_context: SpanContext::new(
+                TraceId::from(1u128),
+                SpanId::from(2u64),
+                TraceFlags::SAMPLED,
+                false,
+                TraceState::default(),
+            ),
+            parent_span_id: SpanId::INVALID,
+            parent_span_is_remote: false,
+            span_kind: SpanKind::Server,
+            name: "http_request".into(),
+            start_time: UNIX_EPOCH,
+            end_time: UNIX_EPOCH + Duration::from_millis(5),
+            attributes: vec![KeyValue::new("http.method", "GET"), KeyValue::new("rows", 3i64)],
+            dropped_attributes_count: 0,
+            events: SpanEvents::default(),
+            links: SpanLinks::default(),
+            status: Status::Unset,
+            instrumentation_scope: Default::default(),
+        };
+
+        let buffer = Buffer::default();
+        let mut exporter = JsonLinesExporter::new(Box::new(buffer.clone()));
+        exporter.set_resource(&Resource::builder_empty().with_service_name("test").build());
+        exporter.export(vec![span]).await.unwrap();
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let line: serde_json::Value = serde_json::from_str(output.trim()).unwrap();
+        let resource = &line["resourceSpans"][0]["resource"]["attributes"][0];
+        assert_eq!(resource["key"], "service.name");
+        let span = &line["resourceSpans"][0]["scopeSpans"][0]["spans"][0];
+        assert_eq!(span["traceId"], "00000000000000000000000000000001");
+        assert_eq!(span["name"], "http_request");
+        assert_eq!(span["kind"], 2);
+        assert_eq!(span["endTimeUnixNano"], "5000000");
+    }
+}
@@ -1,10 +1,6 @@
-use std::sync::OnceLock;
 use crate::db_bridge::DbPool;
 
-/// 获取全局测试连接池（单例模式）
+/// 获取全局测试连接池（单例模式），默认为内存 SQLite，见 `TEST_DATABASE_URL`
 pub fn get_test_pool() -> &'static DbPool {
-    static POOL: OnceLock<DbPool> = OnceLock::new();
-    POOL.get_or_init(|| {
-        crate::db_bridge::establish_connection_pool()
-    })
-}
\ No newline at end of file
+    crate::db_bridge::get_test_pool()
+}
@@ -0,0 +1,705 @@
+use crate::metrics::Metrics;
+use axum::extract::ws::Message;
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{broadcast, mpsc};
+
+/// 频道名最大长度
+const MAX_CHANNEL_LEN: usize = 128;
+
+/// 消息中心配置
+#[derive(Debug, Clone)]
+pub struct HubConfig {
+    /// 每个频道保留的最近消息数，新订阅者加入时重放
+    pub history_size: usize,
+    /// 每个连接待发送消息的队列长度，客户端读取过慢、队列写满时丢弃新消息
+    pub send_queue: usize,
+    /// 保留的最近事件数（所有频道合计），SSE 客户端按 `Last-Event-ID` 续传
+    pub event_buffer: usize,
+    /// 客户端订阅和发布频道的权限
+    pub policy: ChannelPolicy,
+}
+
+impl Default for HubConfig {
+    fn default() -> Self {
+        Self {
+            history_size: 50,
+            send_queue: 100,
+            event_buffer: 1000,
+            policy: ChannelPolicy::default(),
+        }
+    }
+}
+
+impl HubConfig {
+    /// 从环境变量读取配置（`WS_CHANNEL_HISTORY` / `WS_SEND_QUEUE` / `SSE_REPLAY_BUFFER`，频道权限见 [`ChannelPolicy::from_env`]）
+    pub fn from_env() -> Self {
+        dotenvy::dotenv().ok();
+        let var = |key: &str| std::env::var(key).ok().and_then(|v| v.parse::<usize>().ok());
+        let defaults = Self::default();
+        Self {
+            history_size: var("WS_CHANNEL_HISTORY").unwrap_or(defaults.history_size),
+            send_queue: var("WS_SEND_QUEUE")
+                .filter(|&n| n > 0)
+                .unwrap_or(defaults.send_queue),
+            event_buffer: var("SSE_REPLAY_BUFFER").unwrap_or(defaults.event_buffer),
+            policy: ChannelPolicy::from_env(),
+        }
+    }
+}
+
+/// 客户端可以向哪些频道发布消息（脚本和 LISTEN/NOTIFY 不受限制）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PublishPolicy {
+    /// 任何连接
+    All,
+    /// 只有已认证的连接
+    Authenticated,
+    /// 客户端不能发布，只能由服务端推送
+    None,
+}
+
+/// 频道访问控制
+///
+/// 以 `private_prefixes` 中的前缀开头的频道是私有频道，如 `user:42` 只允许身份为 `42` 的连接
+/// 订阅、查询成员和发布；其他频道任何连接都可以订阅，发布权限由 `publish` 决定。
+#[derive(Debug, Clone)]
+pub struct ChannelPolicy {
+    pub private_prefixes: Vec<String>,
+    pub publish: PublishPolicy,
+}
+
+impl Default for ChannelPolicy {
+    fn default() -> Self {
+        Self {
+            private_prefixes: vec!["user:".to_string()],
+            publish: PublishPolicy::All,
+        }
+    }
+}
+
+impl ChannelPolicy {
+    /// 从环境变量读取配置（`WS_PRIVATE_CHANNEL_PREFIXES` / `WS_CLIENT_PUBLISH`）
+    pub fn from_env() -> Self {
+        dotenvy::dotenv().ok();
+        let defaults = Self::default();
+        let private_prefixes = match std::env::var("WS_PRIVATE_CHANNEL_PREFIXES") {
+            Ok(v) => v.split(',').map(str::trim).filter(|p| !p.is_empty()).map(str::to_string).collect(),
+            Err(_) => defaults.private_prefixes,
+        };
+        let publish = match std::env::var("WS_CLIENT_PUBLISH").as_deref() {
+            Ok("authenticated") => PublishPolicy::Authenticated,
+            Ok("none") => PublishPolicy::None,
+            _ => defaults.publish,
+        };
+        Self {
+            private_prefixes,
+            publish,
+        }
+    }
+
+    /// 私有频道的所有者，普通频道返回 `None`
+    fn owner<'a>(&self, channel: &'a str) -> Option<&'a str> {
+        self.private_prefixes
+            .iter()
+            .find_map(|prefix| channel.strip_prefix(prefix.as_str()))
+    }
+
+    /// `user` 为连接的认证身份（subject），匿名连接为 `None`
+    pub fn can_subscribe(&self, user: Option<&str>, channel: &str) -> bool {
+        self.owner(channel).is_none_or(|owner| user == Some(owner))
+    }
+
+    pub fn can_publish(&self, user: Option<&str>, channel: &str) -> bool {
+        let allowed = match self.publish {
+            PublishPolicy::All => true,
+            PublishPolicy::Authenticated => user.is_some(),
+            PublishPolicy::None => false,
+        };
+        allowed && self.can_subscribe(user, channel)
+    }
+}
+
+/// 频道成员
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Member {
+    pub id: String,
+    /// 已认证连接的身份标识
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum HubError {
+    /// 频道名为空、过长或包含控制字符
+    InvalidChannel(String),
+    /// 连接不存在或已断开
+    UnknownConnection(String),
+    /// 没有订阅或发布该频道的权限
+    Forbidden(String),
+}
+
+impl fmt::Display for HubError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HubError::InvalidChannel(name) => write!(f, "invalid channel name \"{}\"", name),
+            HubError::UnknownConnection(id) => write!(f, "unknown connection \"{}\"", id),
+            HubError::Forbidden(name) => write!(f, "access to channel \"{}\" denied", name),
+        }
+    }
+}
+
+impl std::error::Error for HubError {}
+
+pub(crate) fn validate_channel(name: &str) -> Result<(), HubError> {
+    if name.is_empty() || name.len() > MAX_CHANNEL_LEN || name.chars().any(char::is_control) {
+        return Err(HubError::InvalidChannel(name.to_string()));
+    }
+    Ok(())
+}
+
+fn text(value: Value) -> Message {
+    Message::Text(value.to_string().into())
+}
+
+struct Connection {
+    tx: mpsc::Sender<Message>,
+    /// 队列写满后丢弃的消息数，见 [`ConnectionQueue::take_dropped`]
+    dropped: Arc<AtomicU64>,
+    user: Option<String>,
+    channels: BTreeSet<String>,
+}
+
+impl Connection {
+    fn member(&self, id: &str) -> Member {
+        Member {
+            id: id.to_string(),
+            user: self.user.clone(),
+        }
+    }
+
+    /// 放入发送队列，队列已满时丢弃并计入 `ws_lagged_messages_total`
+    fn deliver(&self, frame: &Message) -> bool {
+        match self.tx.try_send(frame.clone()) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                Metrics::global().ws_lagged_messages.inc();
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                false
+            }
+            Err(TrySendError::Closed(_)) => false,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Channel {
+    members: BTreeSet<String>,
+    /// 最近的 `message` 帧，按发布顺序
+    history: VecDeque<Message>,
+}
+
+#[derive(Default)]
+struct HubInner {
+    connections: HashMap<String, Connection>,
+    channels: HashMap<String, Channel>,
+}
+
+impl HubInner {
+    /// 发给频道中除 `except` 外的所有成员，返回成功放入队列的数量
+    fn deliver_to_channel(&self, channel: &str, frame: &Message, except: Option<&str>) -> usize {
+        let Some(ch) = self.channels.get(channel) else {
+            return 0;
+        };
+        ch.members
+            .iter()
+            .filter(|id| Some(id.as_str()) != except)
+            .filter_map(|id| self.connections.get(id))
+            .filter(|conn| conn.deliver(frame))
+            .count()
+    }
+
+    fn members(&self, channel: &str) -> Vec<Member> {
+        self.channels
+            .get(channel)
+            .map(|ch| {
+                ch.members
+                    .iter()
+                    .filter_map(|id| self.connections.get(id).map(|conn| conn.member(id)))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// 退出频道并通知其余成员；频道没有成员时删除（历史消息一并丢弃）
+    fn leave(&mut self, id: &str, channel: &str) -> bool {
+        let Some(ch) = self.channels.get_mut(channel) else {
+            return false;
+        };
+        if !ch.members.remove(id) {
+            return false;
+        }
+        if ch.members.is_empty() {
+            self.channels.remove(channel);
+        } else {
+            let frame = text(json!({ "type": "leave", "channel": channel, "id": id }));
+            self.deliver_to_channel(channel, &frame, None);
+        }
+        true
+    }
+}
+
+/// 连接的发送队列，由连接的发送任务读取
+pub struct ConnectionQueue {
+    rx: mpsc::Receiver<Message>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl ConnectionQueue {
+    pub async fn recv(&mut self) -> Option<Message> {
+        self.rx.recv().await
+    }
+
+    #[cfg(test)]
+    pub fn try_recv(&mut self) -> Option<Message> {
+        self.rx.try_recv().ok()
+    }
+
+    /// 取出并清零队列写满后丢弃的消息数
+    pub fn take_dropped(&self) -> u64 {
+        self.dropped.swap(0, Ordering::Relaxed)
+    }
+}
+
+/// 频道消息或广播，供 SSE 连接使用
+#[derive(Debug, Clone, PartialEq)]
+pub struct HubEvent {
+    /// 递增的事件 ID，即 SSE 的 `id`；服务重启后从 1 重新开始
+    pub id: u64,
+    /// 广播为 `None`
+    pub channel: Option<String>,
+    /// 与 WebSocket 连接收到的帧相同的文本
+    pub data: String,
+}
+
+/// 最近的事件，以及订阅之后的新事件
+pub struct EventSubscription {
+    /// `Last-Event-ID` 之后仍在缓冲区中的事件
+    pub replay: Vec<HubEvent>,
+    /// 缓冲区已经不包含 `Last-Event-ID` 之后的全部事件
+    pub missed: bool,
+    pub rx: broadcast::Receiver<HubEvent>,
+}
+
+#[derive(Default)]
+struct EventLog {
+    last_id: u64,
+    recent: VecDeque<HubEvent>,
+}
+
+/// WebSocket 消息中心
+///
+/// 管理连接、频道成员和频道历史。每个连接有一个有界发送队列，所有发给它的帧
+/// （回复、频道消息、定向消息、广播）都经过这个队列，保证顺序。频道消息和广播
+/// 同时写入事件缓冲区，供 SSE 连接读取，见 [`Hub::events_since`]。
+pub struct Hub {
+    config: HubConfig,
+    inner: Mutex<HubInner>,
+    log: Mutex<EventLog>,
+    events: broadcast::Sender<HubEvent>,
+}
+
+impl Hub {
+    pub fn new(config: HubConfig) -> Self {
+        let (events, _) = broadcast::channel(config.send_queue);
+        Self {
+            config,
+            inner: Mutex::new(HubInner::default()),
+            log: Mutex::new(EventLog::default()),
+            events,
+        }
+    }
+
+    /// 记录一个事件并通知 SSE 连接；持有锁发送，保证与 [`Hub::events_since`] 的快照不重不漏
+    fn record(&self, channel: Option<&str>, data: &str) {
+        let mut log = self.log.lock().unwrap();
+        log.last_id += 1;
+        let event = HubEvent {
+            id: log.last_id,
+            channel: channel.map(str::to_string),
+            data: data.to_string(),
+        };
+        if self.config.event_buffer > 0 {
+            if log.recent.len() == self.config.event_buffer {
+                log.recent.pop_front();
+            }
+            log.recent.push_back(event.clone());
+        }
+        // 没有 SSE 连接时发送失败，忽略
+        let _ = self.events.send(event);
+    }
+
+    /// 订阅事件，并取出 `last_id` 之后仍在缓冲区中的事件
+    ///
+    /// `last_id` 为 `None` 时不重放。`last_id` 比缓冲区中最早的事件还旧，或者大于当前最大 ID
+    /// （服务已重启）时，重放整个缓冲区并把 `missed` 置为 true。
+    pub fn events_since(&self, last_id: Option<u64>) -> EventSubscription {
+        let log = self.log.lock().unwrap();
+        let rx = self.events.subscribe();
+        let Some(last_id) = last_id else {
+            return EventSubscription {
+                replay: Vec::new(),
+                missed: false,
+                rx,
+            };
+        };
+        let oldest = log.recent.front().map_or(log.last_id + 1, |event| event.id);
+        let restarted = last_id > log.last_id;
+        let missed = restarted || last_id + 1 < oldest;
+        let replay = log
+            .recent
+            .iter()
+            .filter(|event| restarted || event.id > last_id)
+            .cloned()
+            .collect();
+        EventSubscription { replay, missed, rx }
+    }
+
+    /// 登记新连接，返回连接 ID 和发送队列的接收端；队列中第一帧是 `welcome`
+    pub fn register(&self, user: Option<String>) -> (String, ConnectionQueue) {
+        let id = hex::encode(rand::random::<[u8; 8]>());
+        let (tx, rx) = mpsc::channel(self.config.send_queue);
+        let dropped = Arc::new(AtomicU64::new(0));
+        let conn = Connection {
+            tx,
+            dropped: dropped.clone(),
+            user,
+            channels: BTreeSet::new(),
+        };
+        conn.deliver(&text(json!({ "type": "welcome", "id": id })));
+        self.inner.lock().unwrap().connections.insert(id.clone(), conn);
+        (id, ConnectionQueue { rx, dropped })
+    }
+
+    /// 连接断开时退出所有频道
+    pub fn unregister(&self, id: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(conn) = inner.connections.remove(id) {
+            for channel in &conn.channels {
+                inner.leave(id, channel);
+            }
+        }
+    }
+
+    /// 订阅频道
+    ///
+    /// 先回复 `subscribed`（带当前成员列表），`replay` 为 true 时随后重放频道历史，
+    /// 并向其他成员发送 `join`。重复订阅只回复，不重复通知。
+    pub fn subscribe(&self, id: &str, channel: &str, replay: bool) -> Result<(), HubError> {
+        validate_channel(channel)?;
+        let mut inner = self.inner.lock().unwrap();
+        let inner = &mut *inner;
+        let conn = inner
+            .connections
+            .get_mut(id)
+            .ok_or_else(|| HubError::UnknownConnection(id.to_string()))?;
+        if !self.config.policy.can_subscribe(conn.user.as_deref(), channel) {
+            return Err(HubError::Forbidden(channel.to_string()));
+        }
+        let joined = conn.channels.insert(channel.to_string());
+        let member = conn.member(id);
+        inner
+            .channels
+            .entry(channel.to_string())
+            .or_default()
+            .members
+            .insert(id.to_string());
+
+        let conn = &inner.connections[id];
+        let members = inner.members(channel);
+        conn.deliver(&text(json!({ "type": "subscribed", "channel": channel, "members": members })));
+        if replay {
+            for frame in &inner.channels[channel].history {
+                conn.deliver(frame);
+            }
+        }
+        if joined {
+            let frame = text(json!({ "type": "join", "channel": channel, "member": member }));
+            inner.deliver_to_channel(channel, &frame, Some(id));
+        }
+        Ok(())
+    }
+
+    /// 退出频道，回复 `unsubscribed` 并向其余成员发送 `leave`；未订阅时返回 false
+    pub fn unsubscribe(&self, id: &str, channel: &str) -> Result<bool, HubError> {
+        let mut inner = self.inner.lock().unwrap();
+        let conn = inner
+            .connections
+            .get_mut(id)
+            .ok_or_else(|| HubError::UnknownConnection(id.to_string()))?;
+        if !conn.channels.remove(channel) {
+            return Ok(false);
+        }
+        conn.deliver(&text(json!({ "type": "unsubscribed", "channel": channel })));
+        inner.leave(id, channel);
+        Ok(true)
+    }
+
+    /// 向频道发布消息，返回收到消息的连接数
+    ///
+    /// 消息同时写入频道历史；没有成员的频道不保留历史。`from` 为发送方连接 ID，按
+    /// [`ChannelPolicy::can_publish`] 检查权限；服务端（脚本）发布时为 `None`，不受限制。
+    pub fn publish(&self, channel: &str, data: Value, from: Option<&str>) -> Result<usize, HubError> {
+        validate_channel(channel)?;
+        if let Some(from) = from {
+            let inner = self.inner.lock().unwrap();
+            let conn = inner
+                .connections
+                .get(from)
+                .ok_or_else(|| HubError::UnknownConnection(from.to_string()))?;
+            if !self.config.policy.can_publish(conn.user.as_deref(), channel) {
+                return Err(HubError::Forbidden(channel.to_string()));
+            }
+        }
+        let mut frame = json!({ "type": "message", "channel": channel, "data": data });
+        if let Some(from) = from {
+            frame["from"] = json!(from);
+        }
+        let data = frame.to_string();
+        self.record(Some(channel), &data);
+        let frame = Message::Text(data.into());
+
+        let mut inner = self.inner.lock().unwrap();
+        let history_size = self.config.history_size;
+        let Some(ch) = inner.channels.get_mut(channel) else {
+            return Ok(0);
+        };
+        if history_size > 0 {
+            if ch.history.len() == history_size {
+                ch.history.pop_front();
+            }
+            ch.history.push_back(frame.clone());
+        }
+        Ok(inner.deliver_to_channel(channel, &frame, None))
+    }
+
+    /// 发给所有连接，返回收到消息的连接数；`frame` 可以是文本或二进制，只有文本会发给 SSE 连接
+    pub fn broadcast(&self, frame: impl Into<Message>) -> usize {
+        let frame = frame.into();
+        if let Message::Text(text) = &frame {
+            self.record(None, text.as_str());
+        }
+        let inner = self.inner.lock().unwrap();
+        inner.connections.values().filter(|conn| conn.deliver(&frame)).count()
+    }
+
+    /// 发给指定连接
+    pub fn send_to(&self, id: &str, frame: impl Into<Message>) -> Result<(), HubError> {
+        let inner = self.inner.lock().unwrap();
+        let conn = inner
+            .connections
+            .get(id)
+            .ok_or_else(|| HubError::UnknownConnection(id.to_string()))?;
+        conn.deliver(&frame.into());
+        Ok(())
+    }
+
+    /// 频道当前成员，按连接 ID 排序；`id` 为查询方连接，私有频道只有所有者可以查询
+    pub fn presence(&self, id: &str, channel: &str) -> Result<Vec<Member>, HubError> {
+        validate_channel(channel)?;
+        let inner = self.inner.lock().unwrap();
+        let conn = inner
+            .connections
+            .get(id)
+            .ok_or_else(|| HubError::UnknownConnection(id.to_string()))?;
+        if !self.config.policy.can_subscribe(conn.user.as_deref(), channel) {
+            return Err(HubError::Forbidden(channel.to_string()));
+        }
+        Ok(inner.members(channel))
+    }
+
+    /// 客户端订阅和发布频道的权限
+    pub fn policy(&self) -> &ChannelPolicy {
+        &self.config.policy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hub(history_size: usize) -> Hub {
+        Hub::new(HubConfig {
+            history_size,
+            send_queue: 16,
+            event_buffer: 3,
+            policy: ChannelPolicy::default(),
+        })
+    }
+
+    fn drain(rx: &mut ConnectionQueue) -> Vec<Value> {
+        let mut frames = Vec::new();
+        while let Some(frame) = rx.try_recv() {
+            frames.push(serde_json::from_str(frame.to_text().unwrap()).unwrap());
+        }
+        frames
+    }
+
+    #[test]
+    fn test_subscribe_publish_and_presence() {
+        let hub = hub(10);
+        let (a, mut rx_a) = hub.register(Some("alice".to_string()));
+        let (b, mut rx_b) = hub.register(None);
+        assert_eq!(drain(&mut rx_a)[0]["type"], "welcome");
+        drain(&mut rx_b);
+
+        hub.subscribe(&a, "room", true).unwrap();
+        hub.subscribe(&b, "room", true).unwrap();
+        let frames = drain(&mut rx_a);
+        assert_eq!(frames[0]["type"], "subscribed");
+        assert_eq!(frames[1]["type"], "join");
+        assert_eq!(frames[1]["member"]["id"], json!(b));
+        assert_eq!(drain(&mut rx_b)[0]["members"].as_array().unwrap().len(), 2);
+
+        assert_eq!(hub.publish("room", json!({ "n": 1 }), Some(&a)).unwrap(), 2);
+        assert_eq!(hub.publish("other", json!(2), None).unwrap(), 0);
+        let frames = drain(&mut rx_b);
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0]["data"]["n"], 1);
+        assert_eq!(frames[0]["from"], json!(a));
+
+        let presence = hub.presence(&b, "room").unwrap();
+        assert_eq!(presence.len(), 2);
+        assert!(presence.iter().any(|m| m.user.as_deref() == Some("alice")));
+
+        hub.unregister(&a);
+        let frames = drain(&mut rx_b);
+        assert_eq!(frames[0]["type"], "leave");
+        assert_eq!(hub.presence(&b, "room").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_private_channels() {
+        let hub = Hub::new(HubConfig {
+            policy: ChannelPolicy {
+                publish: PublishPolicy::Authenticated,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let (alice, mut rx_alice) = hub.register(Some("42".to_string()));
+        let (anon, _rx_anon) = hub.register(None);
+        let (bob, _rx_bob) = hub.register(Some("7".to_string()));
+
+        assert!(hub.subscribe(&alice, "user:42", true).is_ok());
+        assert_eq!(hub.subscribe(&anon, "user:42", true), Err(HubError::Forbidden("user:42".to_string())));
+        assert!(matches!(hub.subscribe(&bob, "user:42", true), Err(HubError::Forbidden(_))));
+        assert!(matches!(hub.presence(&anon, "user:42"), Err(HubError::Forbidden(_))));
+
+        // 客户端不能冒充服务端向别人的私有频道发布，服务端发布不受限制
+        assert!(matches!(hub.publish("user:42", json!(1), Some(&bob)), Err(HubError::Forbidden(_))));
+        drain(&mut rx_alice);
+        assert_eq!(hub.publish("user:42", json!(2), None), Ok(1));
+        assert_eq!(drain(&mut rx_alice)[0]["data"], 2);
+
+        // 匿名连接可以订阅公共频道，但按发布策略不能发布
+        assert!(hub.subscribe(&anon, "room", true).is_ok());
+        assert!(matches!(hub.publish("room", json!(3), Some(&anon)), Err(HubError::Forbidden(_))));
+        assert_eq!(hub.publish("room", json!(4), Some(&bob)), Ok(1));
+    }
+
+    #[test]
+    fn test_history_replay() {
+        let hub = hub(2);
+        let (a, _rx_a) = hub.register(None);
+        hub.subscribe(&a, "news", false).unwrap();
+        for n in 0..3 {
+            hub.publish("news", json!(n), None).unwrap();
+        }
+
+        let (b, mut rx_b) = hub.register(None);
+        hub.subscribe(&b, "news", true).unwrap();
+        let data: Vec<Value> = drain(&mut rx_b)
+            .into_iter()
+            .filter(|f| f["type"] == "message")
+            .map(|f| f["data"].clone())
+            .collect();
+        assert_eq!(data, vec![json!(1), json!(2)]);
+
+        let (c, mut rx_c) = hub.register(None);
+        hub.subscribe(&c, "news", false).unwrap();
+        assert!(drain(&mut rx_c).iter().all(|f| f["type"] != "message"));
+    }
+
+    #[test]
+    fn test_send_to_and_unsubscribe() {
+        let hub = hub(0);
+        let (a, mut rx_a) = hub.register(None);
+        drain(&mut rx_a);
+        hub.send_to(&a, "hello").unwrap();
+        assert_eq!(rx_a.try_recv(), Some(Message::from("hello")));
+        assert!(matches!(hub.send_to("missing", "x"), Err(HubError::UnknownConnection(_))));
+
+        hub.subscribe(&a, "room", true).unwrap();
+        assert!(hub.unsubscribe(&a, "room").unwrap());
+        assert!(!hub.unsubscribe(&a, "room").unwrap());
+        assert!(hub.presence(&a, "room").unwrap().is_empty());
+        assert!(matches!(hub.subscribe(&a, "", true), Err(HubError::InvalidChannel(_))));
+    }
+
+    #[test]
+    fn test_full_queue_drops_messages() {
+        let hub = Hub::new(HubConfig {
+            history_size: 0,
+            send_queue: 2,
+            event_buffer: 0,
+            policy: ChannelPolicy::default(),
+        });
+        // welcome 占用一个位置
+        let (_a, mut rx_a) = hub.register(None);
+        assert_eq!(hub.broadcast("one"), 1);
+        assert_eq!(hub.broadcast("two"), 0);
+        assert_eq!(hub.broadcast(vec![3u8]), 0);
+        rx_a.try_recv().unwrap();
+        assert_eq!(rx_a.try_recv(), Some(Message::from("one")));
+        assert_eq!(rx_a.try_recv(), None);
+        assert_eq!(rx_a.take_dropped(), 2);
+        assert_eq!(rx_a.take_dropped(), 0);
+    }
+
+    #[test]
+    fn test_events_since() {
+        let hub = hub(0);
+        let mut live = hub.events_since(None);
+        for n in 1..=4 {
+            hub.publish("room", json!(n), None).unwrap();
+        }
+        hub.broadcast("hi");
+        hub.broadcast(vec![1u8]);
+
+        let event = live.rx.try_recv().unwrap();
+        assert_eq!(event.id, 1);
+        assert_eq!(event.channel.as_deref(), Some("room"));
+        assert_eq!(
+            serde_json::from_str::<Value>(&event.data).unwrap(),
+            json!({ "type": "message", "channel": "room", "data": 1 })
+        );
+
+        // 缓冲区只保留最近 3 个事件：3、4、5（广播）
+        let ids = |sub: &EventSubscription| sub.replay.iter().map(|e| e.id).collect::<Vec<_>>();
+        let resumed = hub.events_since(Some(3));
+        assert_eq!((ids(&resumed), resumed.missed), (vec![4, 5], false));
+        assert_eq!(resumed.replay[1].channel, None);
+        let resumed = hub.events_since(Some(1));
+        assert_eq!((ids(&resumed), resumed.missed), (vec![3, 4, 5], true));
+        let resumed = hub.events_since(Some(5));
+        assert_eq!((ids(&resumed), resumed.missed), (vec![], false));
+        let resumed = hub.events_since(Some(42));
+        assert_eq!((ids(&resumed), resumed.missed), (vec![3, 4, 5], true));
+    }
+}
@@ -0,0 +1,311 @@
+use crate::rate_limit::TrustedProxies;
+use axum::extract::ConnectInfo;
+use axum::extract::ws::{CloseFrame, Message, WebSocket, close_code};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use futures_util::stream::{SplitStream, StreamExt};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::time::{Interval, MissedTickBehavior};
+
+/// 客户端读取过慢、发送队列写满时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LagPolicy {
+    /// 丢弃消息，队列有空位后发送 `{"type":"lagged","dropped":n}` 通知客户端
+    Notify,
+    /// 以 1008 关闭连接，关闭原因中带丢弃的消息数
+    Disconnect,
+}
+
+/// WebSocket 连接配置
+#[derive(Debug, Clone)]
+pub struct WsConfig {
+    /// 服务端发送 Ping 的间隔，`None` 表示不发送
+    pub ping_interval: Option<Duration>,
+    /// 发送 Ping 后等待的时间，超过 `ping_interval + pong_timeout` 未收到任何帧视为对端已断开
+    pub pong_timeout: Duration,
+    /// 单条消息（及单帧）最大字节数
+    pub max_message_size: usize,
+    /// 同时打开的连接数上限，0 表示不限制
+    ///
+    /// 每个脚本连接占用一个线程和一个 isolate，默认限制以免单个客户端耗尽线程和内存。
+    pub max_connections: usize,
+    /// 单个客户端 IP 的连接数上限，0 表示不限制
+    pub max_connections_per_ip: usize,
+    /// 脚本连接收发队列和 JSON-RPC 响应队列的长度，写满时以 1008 关闭连接
+    pub message_queue: usize,
+    /// JSON-RPC 模式下每个连接同时执行的调用数上限，超出的调用返回 `-32005`；0 表示不限制
+    pub max_inflight_calls: usize,
+    pub lag_policy: LagPolicy,
+    /// SSE 连接发送保活注释的间隔
+    pub sse_keep_alive: Duration,
+}
+
+impl Default for WsConfig {
+    fn default() -> Self {
+        Self {
+            ping_interval: Some(Duration::from_secs(30)),
+            pong_timeout: Duration::from_secs(10),
+            max_message_size: 64 * 1024,
+            max_connections: 512,
+            max_connections_per_ip: 16,
+            message_queue: 64,
+            max_inflight_calls: 8,
+            lag_policy: LagPolicy::Notify,
+            sse_keep_alive: Duration::from_secs(15),
+        }
+    }
+}
+
+impl WsConfig {
+    /// 从环境变量读取配置
+    ///
+    /// `WS_PING_INTERVAL_SECS`（0 关闭心跳）/ `WS_PONG_TIMEOUT_SECS` / `WS_MAX_MESSAGE_SIZE` /
+    /// `WS_MAX_CONNECTIONS` / `WS_MAX_CONNECTIONS_PER_IP`（0 不限制）/ `WS_MESSAGE_QUEUE` /
+    /// `WS_MAX_INFLIGHT_CALLS`（0 不限制）/ `WS_LAG_POLICY`（`notify` 或 `disconnect`）/
+    /// `SSE_KEEPALIVE_SECS`
+    pub fn from_env() -> Self {
+        dotenvy::dotenv().ok();
+        let var = |key: &str| std::env::var(key).ok().filter(|v| !v.is_empty());
+        let number = |key: &str| var(key).and_then(|v| v.parse::<u64>().ok());
+        let defaults = Self::default();
+        Self {
+            ping_interval: match number("WS_PING_INTERVAL_SECS") {
+                Some(0) => None,
+                Some(secs) => Some(Duration::from_secs(secs)),
+                None => defaults.ping_interval,
+            },
+            pong_timeout: number("WS_PONG_TIMEOUT_SECS")
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.pong_timeout),
+            max_message_size: number("WS_MAX_MESSAGE_SIZE")
+                .filter(|&n| n > 0)
+                .map(|n| n as usize)
+                .unwrap_or(defaults.max_message_size),
+            max_connections: number("WS_MAX_CONNECTIONS")
+                .map(|n| n as usize)
+                .unwrap_or(defaults.max_connections),
+            max_connections_per_ip: number("WS_MAX_CONNECTIONS_PER_IP")
+                .map(|n| n as usize)
+                .unwrap_or(defaults.max_connections_per_ip),
+            message_queue: number("WS_MESSAGE_QUEUE")
+                .filter(|&n| n > 0)
+                .map(|n| n as usize)
+                .unwrap_or(defaults.message_queue),
+            max_inflight_calls: number("WS_MAX_INFLIGHT_CALLS")
+                .map(|n| n as usize)
+                .unwrap_or(defaults.max_inflight_calls),
+            lag_policy: match var("WS_LAG_POLICY").as_deref() {
+                Some("disconnect") => LagPolicy::Disconnect,
+                _ => defaults.lag_policy,
+            },
+            sse_keep_alive: number("SSE_KEEPALIVE_SECS")
+                .filter(|&secs| secs > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.sse_keep_alive),
+        }
+    }
+
+    /// 每个 JSON-RPC 连接的调用并发名额
+    pub fn inflight_calls(&self) -> Semaphore {
+        match self.max_inflight_calls {
+            0 => Semaphore::new(Semaphore::MAX_PERMITS),
+            n => Semaphore::new(n),
+        }
+    }
+
+    /// 超过这个时间没有收到客户端的任何帧（包括 Pong）时断开
+    pub fn idle_timeout(&self) -> Option<Duration> {
+        self.ping_interval.map(|interval| interval + self.pong_timeout)
+    }
+
+    /// 心跳定时器，第一次 Ping 在一个间隔之后发送
+    pub fn ping_timer(&self) -> Option<Interval> {
+        self.ping_interval.map(|period| {
+            let mut timer = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+            timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            timer
+        })
+    }
+}
+
+/// 队列写满时关闭连接的帧（1008）
+pub fn overflow_frame() -> Message {
+    Message::Close(Some(CloseFrame {
+        code: close_code::POLICY,
+        reason: "message queue full".into(),
+    }))
+}
+
+/// 等待下一次心跳；没有定时器时永远不返回，用于 `select!` 分支
+pub async fn next_ping(timer: &mut Option<Interval>) {
+    match timer {
+        Some(timer) => {
+            timer.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// 读取客户端的下一帧
+///
+/// 连接关闭、读取出错（包括消息超过大小限制）或超过 `idle_timeout` 没有收到任何帧时返回 `None`。
+pub async fn next_frame(receiver: &mut SplitStream<WebSocket>, idle_timeout: Option<Duration>) -> Option<Message> {
+    let frame = match idle_timeout {
+        Some(timeout) => match tokio::time::timeout(timeout, receiver.next()).await {
+            Ok(frame) => frame,
+            Err(_) => {
+                tracing::info!("WebSocket 客户端 {:?} 内无响应，断开连接", timeout);
+                return None;
+            }
+        },
+        None => receiver.next().await,
+    };
+    match frame? {
+        Ok(msg) => Some(msg),
+        Err(e) => {
+            tracing::info!("WebSocket 读取失败: {}", e);
+            None
+        }
+    }
+}
+
+/// 客户端 IP：只有对端是可信代理时才读取 `X-Forwarded-For`，与限流中间件一致
+pub fn client_ip(headers: &HeaderMap, connect_info: Option<&ConnectInfo<SocketAddr>>) -> String {
+    TrustedProxies::global()
+        .client_ip(headers, connect_info.map(|ConnectInfo(addr)| addr.ip()))
+        .map(|ip| ip.to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[derive(Default)]
+struct Counts {
+    total: usize,
+    per_ip: HashMap<String, usize>,
+}
+
+/// 连接数限制
+///
+/// 升级前调用 [`ConnectionLimiter::try_acquire`]，返回的 [`ConnectionPermit`] 随连接一起释放。
+#[derive(Clone)]
+pub struct ConnectionLimiter {
+    max_total: usize,
+    max_per_ip: usize,
+    counts: Arc<Mutex<Counts>>,
+}
+
+/// 拒绝连接的原因
+#[derive(Debug, PartialEq)]
+pub enum LimitExceeded {
+    Total(usize),
+    PerIp(usize),
+}
+
+impl IntoResponse for LimitExceeded {
+    fn into_response(self) -> Response {
+        tracing::warn!("WebSocket connection rejected: {:?}", self);
+        match self {
+            LimitExceeded::Total(_) => (StatusCode::SERVICE_UNAVAILABLE, "Too Many WebSocket Connections").into_response(),
+            LimitExceeded::PerIp(_) => (StatusCode::TOO_MANY_REQUESTS, "Too Many WebSocket Connections").into_response(),
+        }
+    }
+}
+
+impl ConnectionLimiter {
+    pub fn new(config: &WsConfig) -> Self {
+        Self {
+            max_total: config.max_connections,
+            max_per_ip: config.max_connections_per_ip,
+            counts: Arc::default(),
+        }
+    }
+
+    pub fn try_acquire(&self, ip: &str) -> Result<ConnectionPermit, LimitExceeded> {
+        let mut counts = self.counts.lock().unwrap();
+        if self.max_total > 0 && counts.total >= self.max_total {
+            return Err(LimitExceeded::Total(self.max_total));
+        }
+        let per_ip = counts.per_ip.get(ip).copied().unwrap_or(0);
+        if self.max_per_ip > 0 && per_ip >= self.max_per_ip {
+            return Err(LimitExceeded::PerIp(self.max_per_ip));
+        }
+        counts.total += 1;
+        counts.per_ip.insert(ip.to_string(), per_ip + 1);
+        Ok(ConnectionPermit {
+            ip: ip.to_string(),
+            counts: self.counts.clone(),
+        })
+    }
+}
+
+/// 占用的连接名额，drop 时归还
+pub struct ConnectionPermit {
+    ip: String,
+    counts: Arc<Mutex<Counts>>,
+}
+
+impl Drop for ConnectionPermit {
+    fn drop(&mut self) {
+        let mut counts = self.counts.lock().unwrap();
+        counts.total -= 1;
+        if let Some(n) = counts.per_ip.get_mut(&self.ip) {
+            *n -= 1;
+            if *n == 0 {
+                counts.per_ip.remove(&self.ip);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connection_limiter() {
+        let limiter = ConnectionLimiter::new(&WsConfig {
+            max_connections: 3,
+            max_connections_per_ip: 2,
+            ..WsConfig::default()
+        });
+        let a1 = limiter.try_acquire("10.0.0.1").unwrap();
+        let _a2 = limiter.try_acquire("10.0.0.1").unwrap();
+        assert_eq!(limiter.try_acquire("10.0.0.1").err(), Some(LimitExceeded::PerIp(2)));
+        let _b1 = limiter.try_acquire("10.0.0.2").unwrap();
+        assert_eq!(limiter.try_acquire("10.0.0.3").err(), Some(LimitExceeded::Total(3)));
+
+        drop(a1);
+        assert!(limiter.try_acquire("10.0.0.1").is_ok());
+    }
+
+    #[test]
+    fn test_default_limits() {
+        // 默认限制连接数，0 只作为显式的不限制
+        let config = WsConfig::default();
+        assert!(config.max_connections > 0);
+        assert!(config.max_connections_per_ip > 0);
+        assert!(config.message_queue > 0);
+
+        let limiter = ConnectionLimiter::new(&WsConfig {
+            max_connections: 0,
+            max_connections_per_ip: 0,
+            ..WsConfig::default()
+        });
+        let permits: Vec<_> = (0..100).map(|_| limiter.try_acquire("10.0.0.1").unwrap()).collect();
+        assert_eq!(permits.len(), 100);
+    }
+
+    #[test]
+    fn test_idle_timeout() {
+        let config = WsConfig::default();
+        assert_eq!(config.idle_timeout(), Some(Duration::from_secs(40)));
+        let config = WsConfig {
+            ping_interval: None,
+            ..WsConfig::default()
+        };
+        assert_eq!(config.idle_timeout(), None);
+    }
+}
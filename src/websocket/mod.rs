@@ -0,0 +1,379 @@
+use axum::{
+    Extension,
+    extract::{
+        ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade, close_code},
+        ConnectInfo, Path, State,
+    },
+    http::{Extensions, HeaderMap, Method, Uri},
+    response::{IntoResponse, Response},
+};
+use crate::auth::{AuthIdentity, RpcAcl};
+use crate::db_bridge::DbPool;
+use crate::js_bridge::jsonrpc::context::RpcContext;
+use crate::js_bridge::executor::socket_executor::{SocketConfig, SocketExecutor};
+use crate::js_bridge::executor::worker_pool::WorkerPool;
+use crate::js_bridge::models::{JsRequest, JsResponse};
+use crate::js_bridge::ops::socket_ops::{CLOSE_ABNORMAL, CLOSE_NO_STATUS, SocketEvent};
+use crate::metrics::Metrics;
+use crate::rate_limit::RateLimitClient;
+use crate::request_id::RequestId;
+use futures_util::sink::SinkExt;
+use futures_util::stream::StreamExt;
+use serde_json::json;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tokio::sync::Notify;
+
+pub mod hub;
+pub mod limits;
+pub mod pg_listen;
+pub mod protocol;
+pub mod rpc;
+pub mod sse;
+
+use hub::{Hub, HubConfig};
+use limits::{ConnectionLimiter, ConnectionPermit, LagPolicy, LimitExceeded, WsConfig};
+
+/// WebSocket 共享状态
+pub struct WebSocketState {
+    pub hub: Hub,
+    pub config: WsConfig,
+    pub limiter: ConnectionLimiter,
+}
+
+impl WebSocketState {
+    /// 按客户端 IP 占用一个连接名额
+    fn acquire(
+        &self,
+        headers: &HeaderMap,
+        connect_info: Option<&ConnectInfo<SocketAddr>>,
+    ) -> Result<ConnectionPermit, LimitExceeded> {
+        self.limiter.try_acquire(&limits::client_ip(headers, connect_info))
+    }
+
+    /// 占用一个连接名额，并按配置限制升级后的消息大小
+    fn admit(
+        &self,
+        ws: WebSocketUpgrade,
+        headers: &HeaderMap,
+        connect_info: Option<&ConnectInfo<SocketAddr>>,
+    ) -> Result<(WebSocketUpgrade, ConnectionPermit), LimitExceeded> {
+        let permit = self.acquire(headers, connect_info)?;
+        let size = self.config.max_message_size;
+        Ok((ws.max_message_size(size).max_frame_size(size), permit))
+    }
+}
+
+fn header_map(headers: &HeaderMap) -> HashMap<String, String> {
+    headers
+        .iter()
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect()
+}
+
+/// GET /ws - 频道订阅、定向消息和广播
+///
+/// 客户端通过 JSON 控制消息订阅频道，见 [`protocol::ClientMessage`]；其他文本回显给所有连接。
+/// 协商 `jsonrpc-2.0` 子协议的连接改用 JSON-RPC，见 [`rpc`]。
+pub async fn handle_websocket(
+    ws: WebSocketUpgrade,
+    State((pool, state)): State<(DbPool, Arc<WebSocketState>)>,
+    headers: HeaderMap,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
+    user: Option<Extension<AuthIdentity>>,
+    extensions: Extensions,
+) -> Response {
+    let (ws, permit) = match state.admit(ws, &headers, connect_info.as_deref()) {
+        Ok(admitted) => admitted,
+        Err(e) => return e.into_response(),
+    };
+    let ws = ws.protocols([rpc::SUBPROTOCOL]);
+    let user = user.map(|Extension(identity)| identity);
+    // 连接上的所有调用共享升级请求的身份、访问控制列表、请求 ID 和限流桶
+    let rpc_ctx = ws.selected_protocol().is_some().then(|| {
+        RpcContext::new(pool, header_map(&headers))
+            .with_user(user.clone())
+            .with_acl(extensions.get::<Arc<RpcAcl>>().cloned())
+            .with_request_id(extensions.get::<RequestId>().map(|id| id.0.clone()))
+            .with_ws_state(state.clone())
+            .with_rate_limit(extensions.get::<RateLimitClient>().cloned())
+    });
+    let subject = user.map(|identity| identity.subject);
+    ws.on_upgrade(move |socket| handle_socket(socket, state, subject, rpc_ctx, permit))
+        .into_response()
+}
+
+/// 发送队列写满丢弃消息后发给客户端的帧：通知或关闭连接
+fn lag_frame(policy: LagPolicy, dropped: u64) -> Message {
+    match policy {
+        LagPolicy::Notify => Message::Text(json!({ "type": "lagged", "dropped": dropped }).to_string().into()),
+        LagPolicy::Disconnect => Message::Close(Some(CloseFrame {
+            code: close_code::POLICY,
+            reason: format!("client too slow, {} messages dropped", dropped).into(),
+        })),
+    }
+}
+
+async fn handle_socket(
+    socket: WebSocket,
+    state: Arc<WebSocketState>,
+    user: Option<String>,
+    rpc_ctx: Option<RpcContext>,
+    _permit: ConnectionPermit,
+) {
+    let (mut sender, mut receiver) = socket.split();
+    let (id, mut rx) = state.hub.register(user);
+    // JSON-RPC 响应不经过消息中心，避免被转换为通知；响应队列写满时以 1008 关闭连接
+    let (reply_tx, mut reply_rx) = mpsc::channel::<String>(state.config.message_queue);
+    let overflow = Arc::new(Notify::new());
+    let reply_overflow = overflow.clone();
+    let inflight = Arc::new(state.config.inflight_calls());
+    let json_rpc = rpc_ctx.is_some();
+    let mut ping = state.config.ping_timer();
+    let lag_policy = state.config.lag_policy;
+    let idle_timeout = state.config.idle_timeout();
+    Metrics::global().ws_connections.inc();
+
+    let send_task = tokio::spawn(async move {
+        loop {
+            let mut frames = Vec::with_capacity(2);
+            tokio::select! {
+                Some(frame) = rx.recv() => {
+                    let dropped = rx.take_dropped();
+                    if dropped > 0 {
+                        frames.push(lag_frame(lag_policy, dropped));
+                    }
+                    frames.push(frame);
+                }
+                Some(reply) = reply_rx.recv() => frames.push(Message::Text(reply.into())),
+                _ = overflow.notified() => frames.push(limits::overflow_frame()),
+                _ = limits::next_ping(&mut ping) => frames.push(Message::Ping(Default::default())),
+                else => break,
+            }
+            for frame in frames {
+                let closing = matches!(frame, Message::Close(_));
+                let frame = match frame {
+                    Message::Text(text) if json_rpc => Message::Text(protocol::to_notification(&text).into()),
+                    frame => frame,
+                };
+                if sender.send(frame).await.is_err() || closing {
+                    return;
+                }
+            }
+        }
+    });
+
+    let receive_state = state.clone();
+    let receive_id = id.clone();
+    let receive_task = tokio::spawn(async move {
+        while let Some(msg) = limits::next_frame(&mut receiver, idle_timeout).await {
+            match msg {
+                Message::Text(text) => match &rpc_ctx {
+                    // 脚本可能执行较久，每条消息单独处理，不阻塞后续消息；同时执行的调用数受 `inflight` 限制
+                    Some(ctx) => {
+                        let state = receive_state.clone();
+                        let id = receive_id.clone();
+                        let ctx = ctx.clone();
+                        let reply_tx = reply_tx.clone();
+                        let overflow = reply_overflow.clone();
+                        let inflight = inflight.clone();
+                        tokio::spawn(async move {
+                            if let Some(reply) = rpc::handle_message(&state, &id, ctx, &inflight, &text).await
+                                && let Err(TrySendError::Full(_)) = reply_tx.try_send(reply)
+                            {
+                                overflow.notify_one();
+                            }
+                        });
+                    }
+                    None => protocol::handle_text(&receive_state.hub, &receive_id, &text),
+                },
+                Message::Binary(data) if rpc_ctx.is_none() => {
+                    receive_state.hub.broadcast(Message::Binary(data));
+                }
+                Message::Close(_) => {
+                    tracing::info!("客户端断开连接");
+                    break;
+                }
+                _ => {}
+            }
+        }
+    });
+
+    tokio::select! {
+        _ = send_task => {},
+        _ = receive_task => {},
+    }
+    state.hub.unregister(&id);
+    Metrics::global().ws_connections.dec();
+}
+
+/// GET /ws/{*script_path} - 由脚本处理的 WebSocket 连接
+///
+/// 脚本导出 `onOpen(socket)`、`onMessage(socket, data)`、`onClose(socket, code, reason)`，
+/// 每个连接运行在独立的 isolate 中；`request` 为升级请求，`db` 可照常使用。
+pub async fn handle_script_websocket(
+    ws: WebSocketUpgrade,
+    State((pool, ws_state)): State<(DbPool, Arc<WebSocketState>)>,
+    Path(script_name): Path<String>,
+    uri: Uri,
+    headers: HeaderMap,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
+    user: Option<Extension<AuthIdentity>>,
+) -> Response {
+    let script_path = format!("./scripts/{}", script_name);
+    if !std::path::Path::new(&script_path).exists() {
+        return JsResponse::not_found("Script not found").into_response();
+    }
+    let (ws, permit) = match ws_state.admit(ws, &headers, connect_info.as_deref()) {
+        Ok(admitted) => admitted,
+        Err(e) => return e.into_response(),
+    };
+
+    let request = JsRequest::new(Method::GET.to_string(), uri.path().to_string(), header_map(&headers), String::new())
+        .with_user(user.map(|Extension(identity)| identity));
+
+    ws.on_upgrade(move |socket| handle_script_socket(socket, script_path, request, pool, ws_state, permit))
+}
+
+async fn handle_script_socket(
+    socket: WebSocket,
+    script_path: String,
+    request: JsRequest,
+    pool: DbPool,
+    ws_state: Arc<WebSocketState>,
+    _permit: ConnectionPermit,
+) {
+    let (mut sender, mut receiver) = socket.split();
+    // 收发队列都有界：客户端发送快于 `onMessage` 处理，或脚本发送快于客户端读取时，以 1008 关闭连接
+    let (event_tx, event_rx) = mpsc::channel(ws_state.config.message_queue);
+    let (outgoing_tx, mut outgoing_rx) = mpsc::channel(ws_state.config.message_queue);
+    let overflow = Arc::new(Notify::new());
+    let socket_id = hex::encode(rand::random::<[u8; 8]>());
+    let mut ping = ws_state.config.ping_timer();
+    let idle_timeout = ws_state.config.idle_timeout();
+
+    let worker = match SocketExecutor::spawn(SocketConfig {
+        script_path,
+        socket_id,
+        request,
+        db_pool: pool,
+        ws_state,
+        events: event_rx,
+        outgoing: outgoing_tx,
+        overflow: overflow.clone(),
+    }) {
+        Ok(worker) => worker,
+        Err(e) => {
+            tracing::error!("{}", e);
+            let frame = CloseFrame {
+                code: close_code::ERROR,
+                reason: "Script failed to start".into(),
+            };
+            let _ = sender.send(Message::Close(Some(frame))).await;
+            return;
+        }
+    };
+    Metrics::global().ws_connections.inc();
+
+    // 把脚本发出的帧写给客户端；脚本结束后关闭连接
+    let send_overflow = overflow.clone();
+    let mut send_task = tokio::spawn(async move {
+        loop {
+            let msg = tokio::select! {
+                msg = outgoing_rx.recv() => match msg {
+                    Some(msg) => msg,
+                    None => break,
+                },
+                _ = limits::next_ping(&mut ping) => Message::Ping(Default::default()),
+                _ = send_overflow.notified() => limits::overflow_frame(),
+            };
+            let closing = matches!(msg, Message::Close(_));
+            if sender.send(msg).await.is_err() || closing {
+                return;
+            }
+        }
+        let _ = sender.send(Message::Close(None)).await;
+    });
+
+    let mut close = SocketEvent::Close {
+        code: CLOSE_ABNORMAL,
+        reason: String::new(),
+    };
+    while let Some(msg) = limits::next_frame(&mut receiver, idle_timeout).await {
+        match msg {
+            Message::Text(text) => {
+                let event = SocketEvent::Message {
+                    data: text.to_string(),
+                };
+                if let Some(event) = enqueue(&event_tx, event) {
+                    close = event;
+                    break;
+                }
+            }
+            Message::Binary(data) => {
+                let event = SocketEvent::Binary {
+                    data: data.to_vec().into(),
+                };
+                if let Some(event) = enqueue(&event_tx, event) {
+                    close = event;
+                    break;
+                }
+            }
+            Message::Close(frame) => {
+                close = match frame {
+                    Some(frame) => SocketEvent::Close {
+                        code: frame.code,
+                        reason: frame.reason.to_string(),
+                    },
+                    None => SocketEvent::Close {
+                        code: CLOSE_NO_STATUS,
+                        reason: String::new(),
+                    },
+                };
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    // 客户端已经断开，`onClose` 中发送的消息不再写出
+    let overflowed = matches!(close, SocketEvent::Close { code: close_code::POLICY, .. });
+    if overflowed {
+        overflow.notify_one();
+    }
+    // 队列写满时等待脚本腾出位置，仍然把关闭事件交给 `onClose`
+    let linger = WorkerPool::global().script_linger();
+    let _ = tokio::time::timeout(linger, event_tx.send(close)).await;
+    worker.finish(linger).await;
+    if overflowed {
+        // 等待发送任务写出 1008 关闭帧
+        let _ = tokio::time::timeout(linger, &mut send_task).await;
+    }
+    send_task.abort();
+    Metrics::global().ws_connections.dec();
+}
+
+/// 把客户端消息交给脚本；队列写满时返回要交给 `onClose` 的关闭事件，脚本已经结束时丢弃消息
+fn enqueue(event_tx: &mpsc::Sender<SocketEvent>, event: SocketEvent) -> Option<SocketEvent> {
+    match event_tx.try_send(event) {
+        Err(TrySendError::Full(_)) => {
+            tracing::warn!("WebSocket 脚本消息队列已满，断开连接");
+            Some(SocketEvent::Close {
+                code: close_code::POLICY,
+                reason: "message queue full".to_string(),
+            })
+        }
+        _ => None,
+    }
+}
+
+pub fn create_websocket_state() -> Arc<WebSocketState> {
+    let config = WsConfig::from_env();
+    Arc::new(WebSocketState {
+        hub: Hub::new(HubConfig::from_env()),
+        limiter: ConnectionLimiter::new(&config),
+        config,
+    })
+}
\ No newline at end of file
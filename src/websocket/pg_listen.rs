@@ -0,0 +1,300 @@
+use super::WebSocketState;
+use super::hub::validate_channel;
+use crate::db_bridge::{DbPool, next_retry_delay};
+use crate::js_bridge::executor::{RuntimeConfig, ScriptExecutor};
+use crate::js_bridge::models::JsRequest;
+use diesel::pg::{PgConnection, PgNotification};
+use diesel::{Connection, RunQueryDsl};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// 两次检查新通知之间的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 没有通知时用 `SELECT 1` 探测连接的间隔，连接断开后据此重连
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Postgres 标识符的最大长度
+const MAX_PG_CHANNEL_LEN: usize = 63;
+
+/// Postgres 通道到 WebSocket 频道的映射
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelRoute {
+    pub pg_channel: String,
+    /// `None` 表示只交给处理脚本
+    pub ws_channel: Option<String>,
+}
+
+/// LISTEN/NOTIFY 桥接配置
+#[derive(Debug, Clone)]
+pub struct ListenConfig {
+    pub url: String,
+    pub routes: Vec<ChannelRoute>,
+    /// 收到通知时额外执行的脚本（`./scripts/` 下的路径）
+    pub script_path: Option<String>,
+}
+
+impl ListenConfig {
+    /// 从环境变量读取配置，未设置 `PG_LISTEN_CHANNELS` 时返回 `None`
+    ///
+    /// `PG_LISTEN_CHANNELS` 为逗号分隔的通道，`orders` 转发到同名频道，`orders=shop` 转发到 `shop` 频道，
+    /// `orders=` 只交给处理脚本；`PG_LISTEN_URL` 默认为 `DATABASE_URL`，`PG_LISTEN_SCRIPT` 为可选的处理脚本。
+    pub fn from_env() -> Result<Option<Self>, String> {
+        dotenvy::dotenv().ok();
+        let var = |key: &str| std::env::var(key).ok().filter(|v| !v.trim().is_empty());
+        let Some(channels) = var("PG_LISTEN_CHANNELS") else {
+            return Ok(None);
+        };
+        let routes = parse_routes(&channels)?;
+        let url = var("PG_LISTEN_URL")
+            .or_else(|| var("DATABASE_URL"))
+            .unwrap_or_else(|| "postgres://ever@localhost/postgres".to_string());
+        if !url.starts_with("postgres://") && !url.starts_with("postgresql://") {
+            return Err("PG_LISTEN_CHANNELS requires a postgres:// database URL".to_string());
+        }
+        let script_path = var("PG_LISTEN_SCRIPT").map(|script| format!("./scripts/{}", script.trim_start_matches('/')));
+        if script_path.is_none() && routes.iter().any(|route| route.ws_channel.is_none()) {
+            return Err("PG_LISTEN_CHANNELS entries without a WebSocket channel require PG_LISTEN_SCRIPT".to_string());
+        }
+        Ok(Some(Self {
+            url,
+            routes,
+            script_path,
+        }))
+    }
+}
+
+fn parse_routes(value: &str) -> Result<Vec<ChannelRoute>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (pg_channel, ws_channel) = entry.split_once('=').unwrap_or((entry, entry));
+            let (pg_channel, ws_channel) = (pg_channel.trim(), ws_channel.trim());
+            if pg_channel.is_empty() || pg_channel.len() > MAX_PG_CHANNEL_LEN || pg_channel.contains('\0') {
+                return Err(format!("invalid Postgres channel \"{}\"", pg_channel));
+            }
+            let ws_channel = (!ws_channel.is_empty()).then(|| ws_channel.to_string());
+            if let Some(ws_channel) = &ws_channel {
+                validate_channel(ws_channel).map_err(|e| e.to_string())?;
+            }
+            Ok(ChannelRoute {
+                pg_channel: pg_channel.to_string(),
+                ws_channel,
+            })
+        })
+        .collect()
+}
+
+/// 按标识符引用通道名，通道名原样区分大小写
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// 把通知转发到消息中心和处理脚本
+pub struct NotifyBridge {
+    routes: HashMap<String, String>,
+    script_path: Option<String>,
+    pool: DbPool,
+    ws_state: Arc<WebSocketState>,
+}
+
+impl NotifyBridge {
+    pub fn new(config: &ListenConfig, pool: DbPool, ws_state: Arc<WebSocketState>) -> Self {
+        Self {
+            routes: config
+                .routes
+                .iter()
+                .filter_map(|route| Some((route.pg_channel.clone(), route.ws_channel.clone()?)))
+                .collect(),
+            script_path: config.script_path.clone(),
+            pool,
+            ws_state,
+        }
+    }
+
+    /// 处理一条通知
+    ///
+    /// payload 是合法 JSON 时按 JSON 转发，否则按字符串转发；订阅者收到
+    /// `{"type":"message","channel":"<频道>","data":<payload>}`。脚本在后台执行，不阻塞后续通知。
+    pub fn dispatch(&self, notification: PgNotification) {
+        let data = serde_json::from_str::<Value>(&notification.payload)
+            .unwrap_or_else(|_| Value::String(notification.payload.clone()));
+
+        if let Some(ws_channel) = self.routes.get(&notification.channel) {
+            match self.ws_state.hub.publish(ws_channel, data.clone(), None) {
+                Ok(delivered) => tracing::debug!(
+                    "pg notify {} -> ws channel {}: {} connections",
+                    notification.channel,
+                    ws_channel,
+                    delivered
+                ),
+                Err(e) => tracing::warn!("pg notify {}: {}", notification.channel, e),
+            }
+        }
+
+        if let Some(script_path) = &self.script_path {
+            let body = json!({
+                "channel": notification.channel,
+                "payload": data,
+                "process_id": notification.process_id,
+            });
+            let headers = HashMap::from([("content-type".to_string(), "application/json".to_string())]);
+            let config = RuntimeConfig {
+                script_path: script_path.clone(),
+                request: JsRequest::new(
+                    "POST".to_string(),
+                    format!("/pg_notify/{}", notification.channel),
+                    headers,
+                    body.to_string(),
+                ),
+                db_pool: self.pool.clone(),
+                ws_state: Some(self.ws_state.clone()),
+            };
+            let script_path = script_path.clone();
+            tokio::spawn(async move {
+                let response = ScriptExecutor::execute(config).await;
+                if response.status >= 400 {
+                    tracing::warn!(
+                        "pg notify script {} failed for {}: {} {}",
+                        script_path,
+                        notification.channel,
+                        response.status,
+                        response.body
+                    );
+                }
+            });
+        }
+    }
+}
+
+/// 启动 LISTEN 后台任务
+///
+/// 专用线程持有一个 Postgres 连接并轮询通知，连接断开后按指数退避重连并重新 `LISTEN`；
+/// 通知交给 tokio 任务中的 [`NotifyBridge::dispatch`] 处理。
+pub fn spawn_listener(config: ListenConfig, pool: DbPool, ws_state: Arc<WebSocketState>) {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let bridge = NotifyBridge::new(&config, pool, ws_state);
+    tokio::spawn(async move {
+        while let Some(notification) = rx.recv().await {
+            bridge.dispatch(notification);
+        }
+    });
+
+    std::thread::Builder::new()
+        .name("pg-listen".to_string())
+        .spawn(move || {
+            let mut retry_delay = Duration::ZERO;
+            loop {
+                match listen(&config, &tx, &mut retry_delay) {
+                    Ok(()) => return,
+                    Err(e) => {
+                        retry_delay = next_retry_delay(retry_delay);
+                        tracing::warn!("pg listen connection failed, reconnecting in {:?}: {}", retry_delay, e);
+                        std::thread::sleep(retry_delay);
+                    }
+                }
+            }
+        })
+        .expect("Failed to spawn pg-listen thread");
+}
+
+/// 建立连接、订阅所有通道并转发通知，直到连接出错；接收端关闭时返回 `Ok`
+fn listen(
+    config: &ListenConfig,
+    tx: &mpsc::UnboundedSender<PgNotification>,
+    retry_delay: &mut Duration,
+) -> Result<(), String> {
+    let mut conn = PgConnection::establish(&config.url).map_err(|e| e.to_string())?;
+    for route in &config.routes {
+        diesel::sql_query(format!("LISTEN {}", quote_ident(&route.pg_channel)))
+            .execute(&mut conn)
+            .map_err(|e| e.to_string())?;
+    }
+    *retry_delay = Duration::ZERO;
+    let channels: Vec<&str> = config.routes.iter().map(|route| route.pg_channel.as_str()).collect();
+    tracing::info!("listening on Postgres channels {:?}", channels);
+
+    let mut last_check = Instant::now();
+    loop {
+        for notification in conn.notifications_iter() {
+            let notification = notification.map_err(|e| e.to_string())?;
+            if tx.send(notification).is_err() {
+                return Ok(());
+            }
+        }
+        if last_check.elapsed() >= KEEPALIVE_INTERVAL {
+            diesel::sql_query("SELECT 1").execute(&mut conn).map_err(|e| e.to_string())?;
+            last_check = Instant::now();
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::get_test_pool;
+    use crate::websocket::create_websocket_state;
+
+    #[test]
+    fn test_parse_routes() {
+        let routes = parse_routes(" orders , users=user_events,audit=").unwrap();
+        assert_eq!(
+            routes,
+            vec![
+                ChannelRoute {
+                    pg_channel: "orders".to_string(),
+                    ws_channel: Some("orders".to_string()),
+                },
+                ChannelRoute {
+                    pg_channel: "users".to_string(),
+                    ws_channel: Some("user_events".to_string()),
+                },
+                ChannelRoute {
+                    pg_channel: "audit".to_string(),
+                    ws_channel: None,
+                },
+            ]
+        );
+        assert!(parse_routes("=room").is_err());
+        assert!(parse_routes(&"x".repeat(64)).is_err());
+        assert_eq!(quote_ident(r#"a"b"#), r#""a""b""#);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_to_hub() {
+        let state = create_websocket_state();
+        let config = ListenConfig {
+            url: String::new(),
+            routes: parse_routes("orders=shop").unwrap(),
+            script_path: None,
+        };
+        let bridge = NotifyBridge::new(&config, get_test_pool().clone(), state.clone());
+        let (id, mut rx) = state.hub.register(None);
+        state.hub.subscribe(&id, "shop", false).unwrap();
+        while rx.try_recv().is_some() {}
+
+        for payload in [r#"{"id":1}"#, "plain"] {
+            bridge.dispatch(PgNotification {
+                process_id: 1,
+                channel: "orders".to_string(),
+                payload: payload.to_string(),
+            });
+        }
+        let frames: Vec<Value> = std::iter::from_fn(|| rx.try_recv())
+            .map(|frame| serde_json::from_str(frame.to_text().unwrap()).unwrap())
+            .collect();
+        assert_eq!(
+            frames,
+            vec![
+                json!({ "type": "message", "channel": "shop", "data": { "id": 1 } }),
+                json!({ "type": "message", "channel": "shop", "data": "plain" }),
+            ]
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use super::hub::{Hub, HubError};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+/// `/ws` 客户端发来的控制消息（JSON 对象，按 `type` 区分）
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ClientMessage {
+    /// 订阅频道；`history` 为 false 时不重放历史消息
+    Subscribe {
+        channel: String,
+        #[serde(default = "default_history")]
+        history: bool,
+    },
+    Unsubscribe { channel: String },
+    /// 向频道发布消息（不要求已订阅）
+    Publish {
+        channel: String,
+        #[serde(default)]
+        data: Value,
+    },
+    /// 发给指定连接
+    Send {
+        to: String,
+        #[serde(default)]
+        data: Value,
+    },
+    /// 查询频道成员
+    Presence { channel: String },
+}
+
+fn default_history() -> bool {
+    true
+}
+
+fn error_frame(message: impl std::fmt::Display) -> String {
+    json!({ "type": "error", "message": message.to_string() }).to_string()
+}
+
+/// 处理一条文本消息
+///
+/// 带 `type` 字段的 JSON 对象按 [`ClientMessage`] 处理，出错时回复 `error` 帧；
+/// 其他文本保持原来的行为，回显给所有连接。
+pub fn handle_text(hub: &Hub, id: &str, text: &str) {
+    let message = match serde_json::from_str::<Value>(text) {
+        Ok(value) if value.get("type").is_some() => serde_json::from_value::<ClientMessage>(value),
+        _ => {
+            tracing::info!("收到消息: {}", text);
+            hub.broadcast(format!("服务器回复: {}", text));
+            return;
+        }
+    };
+
+    let result = match message {
+        Ok(message) => dispatch(hub, id, message),
+        Err(e) => Err(format!("invalid message: {}", e)),
+    };
+    if let Err(e) = result {
+        let _ = hub.send_to(id, error_frame(e));
+    }
+}
+
+fn dispatch(hub: &Hub, id: &str, message: ClientMessage) -> Result<(), String> {
+    if let ClientMessage::Presence { channel } = &message {
+        let members = hub.presence(id, channel).map_err(|e| e.to_string())?;
+        let frame = json!({ "type": "presence", "channel": channel, "members": members });
+        return hub.send_to(id, frame.to_string()).map_err(|e| e.to_string());
+    }
+    apply(hub, id, message).map(drop)
+}
+
+/// 执行控制消息，返回 JSON-RPC 调用（`ws.subscribe` 等）的结果
+///
+/// `subscribe` / `unsubscribe` / `send` 返回 true，`publish` 返回收到消息的连接数，
+/// `presence` 返回成员列表。
+pub fn apply(hub: &Hub, id: &str, message: ClientMessage) -> Result<Value, String> {
+    let result: Result<Value, HubError> = match message {
+        ClientMessage::Subscribe { channel, history } => hub.subscribe(id, &channel, history).map(|_| json!(true)),
+        ClientMessage::Unsubscribe { channel } => match hub.unsubscribe(id, &channel) {
+            Ok(true) => Ok(json!(true)),
+            Ok(false) => return Err(format!("not subscribed to \"{}\"", channel)),
+            Err(e) => Err(e),
+        },
+        ClientMessage::Publish { channel, data } => hub.publish(&channel, data, Some(id)).map(|n| json!(n)),
+        ClientMessage::Send { to, data } => {
+            let frame = json!({ "type": "direct", "from": id, "data": data }).to_string();
+            hub.send_to(&to, frame).map(|_| json!(true))
+        }
+        ClientMessage::Presence { channel } => hub.presence(id, &channel).map(|members| json!(members)),
+    };
+    result.map_err(|e| e.to_string())
+}
+
+/// 把消息中心的帧转换为 JSON-RPC 通知，供 JSON-RPC 模式的连接使用
+///
+/// `{"type":"message","channel":"room",...}` 转换为
+/// `{"jsonrpc":"2.0","method":"ws.message","params":{"channel":"room",...}}`；
+/// 非 JSON 的文本（回显广播）转换为 `ws.broadcast` 通知。
+pub fn to_notification(frame: &str) -> String {
+    let (method, params) = match serde_json::from_str::<Value>(frame) {
+        Ok(Value::Object(mut params)) => match params.remove("type") {
+            Some(Value::String(kind)) => (format!("ws.{}", kind), Value::Object(params)),
+            _ => ("ws.broadcast".to_string(), json!({ "data": params })),
+        },
+        _ => ("ws.broadcast".to_string(), json!({ "data": frame })),
+    };
+    json!({ "jsonrpc": "2.0", "method": method, "params": params }).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::websocket::hub::HubConfig;
+    use axum::extract::ws::Message;
+
+    #[test]
+    fn test_parse_client_message() {
+        let msg: ClientMessage = serde_json::from_str(r#"{"type":"subscribe","channel":"room"}"#).unwrap();
+        assert_eq!(
+            msg,
+            ClientMessage::Subscribe {
+                channel: "room".to_string(),
+                history: true
+            }
+        );
+        assert!(serde_json::from_str::<ClientMessage>(r#"{"type":"dance"}"#).is_err());
+    }
+
+    #[test]
+    fn test_handle_text() {
+        let hub = Hub::new(HubConfig::default());
+        let (a, mut rx_a) = hub.register(None);
+        let (b, mut rx_b) = hub.register(None);
+        while rx_a.try_recv().is_some() {}
+        while rx_b.try_recv().is_some() {}
+
+        handle_text(&hub, &a, "hello");
+        assert_eq!(rx_b.try_recv(), Some(Message::from("服务器回复: hello")));
+        rx_a.try_recv().unwrap();
+
+        handle_text(&hub, &a, &format!(r#"{{"type":"send","to":"{}","data":"hi"}}"#, b));
+        let frame: Value = serde_json::from_str(rx_b.try_recv().unwrap().to_text().unwrap()).unwrap();
+        assert_eq!(frame, json!({ "type": "direct", "from": a, "data": "hi" }));
+
+        handle_text(&hub, &a, r#"{"type":"unsubscribe","channel":"room"}"#);
+        let frame: Value = serde_json::from_str(rx_a.try_recv().unwrap().to_text().unwrap()).unwrap();
+        assert_eq!(frame["type"], "error");
+    }
+
+    #[test]
+    fn test_to_notification() {
+        let frame = json!({ "type": "message", "channel": "room", "data": 1 }).to_string();
+        let notification: Value = serde_json::from_str(&to_notification(&frame)).unwrap();
+        assert_eq!(
+            notification,
+            json!({ "jsonrpc": "2.0", "method": "ws.message", "params": { "channel": "room", "data": 1 } })
+        );
+        let notification: Value = serde_json::from_str(&to_notification("服务器回复: hi")).unwrap();
+        assert_eq!(notification["method"], "ws.broadcast");
+        assert_eq!(notification["params"]["data"], "服务器回复: hi");
+    }
+}
@@ -0,0 +1,209 @@
+use super::WebSocketState;
+use super::protocol::{self, ClientMessage};
+use crate::js_bridge::jsonrpc::batch_processor::BatchProcessor;
+use crate::js_bridge::jsonrpc::context::RpcContext;
+use crate::js_bridge::jsonrpc::request_parser::{JsonRpcRequestType, RequestParser};
+use crate::js_bridge::jsonrpc::request_validator::RequestValidator;
+use crate::js_bridge::models::{JsonRpcError, JsonRpcRequest, JsonRpcResponse};
+use serde_json::{Value, json};
+use tokio::sync::Semaphore;
+
+/// 客户端通过 `Sec-WebSocket-Protocol` 协商此子协议后，连接进入 JSON-RPC 模式
+pub const SUBPROTOCOL: &str = "jsonrpc-2.0";
+
+/// 由消息中心处理的方法（`ws.subscribe` 等），其余方法按 `/rpc` 的方式执行脚本
+const HUB_METHODS: [&str; 5] = ["subscribe", "unsubscribe", "publish", "send", "presence"];
+
+/// 处理 JSON-RPC 模式连接的一条消息，返回要发回的响应
+///
+/// 单个请求和批量请求与 `/rpc` 一样经过 `RequestValidator` / `BatchProcessor`；
+/// 没有 `id` 的请求是通知，不回复，全部是通知的批量请求返回 `None`。
+/// `inflight` 是连接上同时执行的调用数上限（批量请求的每个元素各占一个名额）。
+pub async fn handle_message(
+    state: &WebSocketState,
+    conn_id: &str,
+    ctx: RpcContext,
+    inflight: &Semaphore,
+    text: &str,
+) -> Option<String> {
+    let response = match RequestParser::parse_json_rpc_request(text) {
+        Ok(JsonRpcRequestType::Single(req)) => json!(call(state, conn_id, req, ctx, inflight).await?),
+        Ok(JsonRpcRequestType::Batch(reqs)) => {
+            if let Err(err) = RequestValidator::validate_batch_not_empty(&reqs) {
+                json!(JsonRpcResponse::error(err, Some(Value::Null)))
+            } else {
+                let calls = reqs.into_iter().map(|req| async {
+                    match req {
+                        Ok(req) => call(state, conn_id, req, ctx.clone(), inflight).await,
+                        Err(err) => Some(err.into_response()),
+                    }
+                });
+                let responses: Vec<JsonRpcResponse> =
+                    futures::future::join_all(calls).await.into_iter().flatten().collect();
+                if responses.is_empty() {
+                    return None;
+                }
+                json!(responses)
+            }
+        }
+        Err(err) => json!(err.into_response()),
+    };
+    Some(response.to_string())
+}
+
+/// 执行一个调用；批量请求中的每个元素各自计入限流和并发上限，超限的元素返回 `-32005`
+async fn call(
+    state: &WebSocketState,
+    conn_id: &str,
+    req: JsonRpcRequest,
+    ctx: RpcContext,
+    inflight: &Semaphore,
+) -> Option<JsonRpcResponse> {
+    let Ok(_permit) = inflight.try_acquire() else {
+        let error = JsonRpcError::too_many_calls().with_request_id(ctx.request_id.as_deref());
+        return (!RequestValidator::is_notification(&req)).then(|| JsonRpcResponse::error(error, req.id));
+    };
+    if let Some(rate_limit) = &ctx.rate_limit {
+        let decision = rate_limit.check_rpc(&req.method);
+        if !decision.allowed {
+            let error = JsonRpcError::rate_limited(decision.retry_after).with_request_id(ctx.request_id.as_deref());
+            return (!RequestValidator::is_notification(&req)).then(|| JsonRpcResponse::error(error, req.id));
+        }
+    }
+    match req.method.strip_prefix("ws.").map(str::to_string) {
+        Some(action) => {
+            let notification = RequestValidator::is_notification(&req);
+            let response = call_hub(state, conn_id, &action, req);
+            (!notification).then_some(response)
+        }
+        None => BatchProcessor::process_single(req, ctx).await,
+    }
+}
+
+/// `ws.<action>` 调用，参数与对应控制消息的字段相同
+fn call_hub(state: &WebSocketState, conn_id: &str, action: &str, req: JsonRpcRequest) -> JsonRpcResponse {
+    if let Err(err) = RequestValidator::validate_request(&req) {
+        return JsonRpcResponse::error(err, Some(req.id.unwrap_or(Value::Null)));
+    }
+    if !HUB_METHODS.contains(&action) {
+        return JsonRpcResponse::error(JsonRpcError::method_not_found(&req.method), req.id);
+    }
+    let mut params = match req.params {
+        Some(Value::Object(params)) => params,
+        None => serde_json::Map::new(),
+        Some(_) => {
+            return JsonRpcResponse::error(JsonRpcError::invalid_params("params must be an object"), req.id);
+        }
+    };
+    params.insert("type".to_string(), json!(action));
+
+    let result = serde_json::from_value::<ClientMessage>(Value::Object(params))
+        .map_err(|e| e.to_string())
+        .and_then(|message| protocol::apply(&state.hub, conn_id, message));
+    match result {
+        Ok(result) => JsonRpcResponse::success(result, req.id),
+        Err(e) => JsonRpcResponse::error(JsonRpcError::invalid_params(&e), req.id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::get_test_pool;
+    use crate::websocket::create_websocket_state;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_hub_methods() {
+        let state = create_websocket_state();
+        let (id, mut rx) = state.hub.register(None);
+        let ctx = RpcContext::new(get_test_pool().clone(), HashMap::new());
+        let inflight = Semaphore::new(8);
+
+        let text = r#"{"jsonrpc":"2.0","method":"ws.subscribe","params":{"channel":"room"},"id":1}"#;
+        let response: Value = serde_json::from_str(&handle_message(&state, &id, ctx.clone(), &inflight, text).await.unwrap()).unwrap();
+        assert_eq!(response, json!({ "jsonrpc": "2.0", "result": true, "id": 1 }));
+
+        let text = r#"[
+            {"jsonrpc":"2.0","method":"ws.presence","params":{"channel":"room"},"id":2},
+            {"jsonrpc":"2.0","method":"ws.publish","params":{"channel":"room","data":"hi"}},
+            {"jsonrpc":"2.0","method":"ws.dance","id":3},
+            {"jsonrpc":"2.0","method":"ws.subscribe","params":{"channel":""},"id":4}
+        ]"#;
+        let responses: Value = serde_json::from_str(&handle_message(&state, &id, ctx.clone(), &inflight, text).await.unwrap()).unwrap();
+        let responses = responses.as_array().unwrap();
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[0]["result"][0]["id"], json!(id));
+        assert_eq!(responses[1]["error"]["code"], -32601);
+        assert_eq!(responses[2]["error"]["code"], -32602);
+
+        let frames: Vec<_> = std::iter::from_fn(|| rx.try_recv()).collect();
+        assert!(frames.iter().any(|f| f.to_text().unwrap().contains(r#""data":"hi""#)));
+
+        let text = r#"{"jsonrpc":"2.0","method":"ws.unsubscribe","params":{"channel":"room"}}"#;
+        assert!(handle_message(&state, &id, ctx.clone(), &inflight, text).await.is_none());
+
+        // 不合法的请求不是通知，缺少 id 时同样回复 "id": null
+        let text = r#"[{"jsonrpc":"1.0","method":"ws.subscribe","params":{"channel":"room"}}]"#;
+        let responses: Value = serde_json::from_str(&handle_message(&state, &id, ctx.clone(), &inflight, text).await.unwrap()).unwrap();
+        assert_eq!(responses[0]["error"]["code"], -32600);
+        assert_eq!(responses[0].get("id"), Some(&Value::Null));
+
+        let response: Value = serde_json::from_str(&handle_message(&state, &id, ctx, &inflight, "not json").await.unwrap()).unwrap();
+        assert_eq!(response["error"]["code"], -32700);
+        assert_eq!(response["id"], Value::Null);
+    }
+
+    #[tokio::test]
+    async fn test_calls_are_rate_limited() {
+        use crate::rate_limit::{Limit, RateLimitClient, RateLimitConfig, RateLimiter};
+        use std::sync::Arc;
+
+        let state = create_websocket_state();
+        let (id, _rx) = state.hub.register(None);
+        let limiter = Arc::new(RateLimiter::new(RateLimitConfig {
+            enabled: true,
+            default_limit: Limit::new(2, 0.001),
+            ..Default::default()
+        }));
+        let ctx = RpcContext::new(get_test_pool().clone(), HashMap::new())
+            .with_rate_limit(Some(RateLimitClient::new(limiter, "ip:1.1.1.1".to_string())));
+        let inflight = Semaphore::new(8);
+
+        // 批量请求中每个元素单独计数，超出的元素返回 -32005
+        let text = r#"[
+            {"jsonrpc":"2.0","method":"ws.presence","params":{"channel":"room"},"id":1},
+            {"jsonrpc":"2.0","method":"ws.presence","params":{"channel":"room"},"id":2},
+            {"jsonrpc":"2.0","method":"ws.presence","params":{"channel":"room"},"id":3}
+        ]"#;
+        let responses: Value = serde_json::from_str(&handle_message(&state, &id, ctx.clone(), &inflight, text).await.unwrap()).unwrap();
+        let codes: Vec<_> = responses.as_array().unwrap().iter().map(|r| r["error"]["code"].clone()).collect();
+        assert_eq!(codes.iter().filter(|c| **c == json!(-32005)).count(), 1);
+
+        let text = r#"{"jsonrpc":"2.0","method":"ws.presence","params":{"channel":"room"},"id":4}"#;
+        let response: Value = serde_json::from_str(&handle_message(&state, &id, ctx, &inflight, text).await.unwrap()).unwrap();
+        assert_eq!(response["error"]["code"], -32005);
+        assert_eq!(response["id"], 4);
+    }
+
+    #[tokio::test]
+    async fn test_inflight_calls_are_capped() {
+        let state = create_websocket_state();
+        let (id, _rx) = state.hub.register(None);
+        let ctx = RpcContext::new(get_test_pool().clone(), HashMap::new());
+        let inflight = Semaphore::new(1);
+
+        let text = r#"{"jsonrpc":"2.0","method":"ws.presence","params":{"channel":"room"},"id":1}"#;
+        let response: Value = serde_json::from_str(&handle_message(&state, &id, ctx.clone(), &inflight, text).await.unwrap()).unwrap();
+        assert!(response.get("result").is_some());
+
+        // 名额被占满时直接返回 -32005，不执行调用
+        let _busy = inflight.try_acquire().unwrap();
+        let response: Value = serde_json::from_str(&handle_message(&state, &id, ctx.clone(), &inflight, text).await.unwrap()).unwrap();
+        assert_eq!(response["error"]["code"], -32005);
+        assert_eq!(response["id"], 1);
+        let text = r#"{"jsonrpc":"2.0","method":"ws.presence","params":{"channel":"room"}}"#;
+        assert!(handle_message(&state, &id, ctx, &inflight, text).await.is_none());
+    }
+
+}
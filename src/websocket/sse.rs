@@ -0,0 +1,160 @@
+use super::WebSocketState;
+use super::hub::{HubEvent, validate_channel};
+use super::limits::ConnectionPermit;
+use crate::auth::AuthIdentity;
+use crate::db_bridge::DbPool;
+use crate::metrics::Metrics;
+use axum::{
+    Extension,
+    extract::{ConnectInfo, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{
+        IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
+};
+use futures::stream::{self, Stream, StreamExt};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+
+#[derive(Debug, Deserialize)]
+pub struct EventsQuery {
+    /// 逗号分隔的频道名
+    #[serde(default)]
+    channels: String,
+}
+
+/// 连接占用的名额和指标，随响应流一起释放
+struct SseConnection {
+    _permit: ConnectionPermit,
+}
+
+impl Drop for SseConnection {
+    fn drop(&mut self) {
+        Metrics::global().sse_connections.dec();
+    }
+}
+
+fn to_event(event: &HubEvent) -> Event {
+    Event::default().id(event.id.to_string()).data(&event.data)
+}
+
+/// 有事件没有送达时发送，客户端应重新获取完整状态
+fn lagged_event() -> Event {
+    Event::default().event("lagged").data(r#"{"type":"lagged"}"#)
+}
+
+fn parse_channels(value: &str) -> Result<HashSet<String>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|channel| !channel.is_empty())
+        .map(|channel| {
+            validate_channel(channel).map_err(|e| e.to_string())?;
+            Ok(channel.to_string())
+        })
+        .collect()
+}
+
+/// GET /events?channels=a,b - 通过 Server-Sent Events 接收频道消息和广播
+///
+/// 数据与 `/ws` 连接收到的帧相同，事件 ID 为消息中心的递增 ID；断线重连时浏览器自动带上
+/// `Last-Event-ID`，从消息中心的事件缓冲区续传。私有频道（如 `user:42`）与 `/ws` 一样只对所有者开放，
+/// 否则返回 403。
+pub async fn handle_events(
+    State((_, state)): State<(DbPool, Arc<WebSocketState>)>,
+    Query(query): Query<EventsQuery>,
+    headers: HeaderMap,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
+    user: Option<Extension<AuthIdentity>>,
+) -> Response {
+    let channels = match parse_channels(&query.channels) {
+        Ok(channels) => channels,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    let subject = user.as_ref().map(|Extension(identity)| identity.subject.as_str());
+    if let Some(channel) = channels.iter().find(|channel| !state.hub.policy().can_subscribe(subject, channel)) {
+        return (StatusCode::FORBIDDEN, format!("access to channel \"{}\" denied", channel)).into_response();
+    }
+    let connection = match state.acquire(&headers, connect_info.as_deref()) {
+        Ok(permit) => SseConnection { _permit: permit },
+        Err(e) => return e.into_response(),
+    };
+    Metrics::global().sse_connections.inc();
+
+    let last_id = headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok());
+    let subscription = state.hub.events_since(last_id);
+    let keep_alive = KeepAlive::new().interval(state.config.sse_keep_alive);
+    Sse::new(events(subscription, channels, connection))
+        .keep_alive(keep_alive)
+        .into_response()
+}
+
+/// 广播总是发送，频道消息只发送订阅的频道
+fn wanted(channels: &HashSet<String>, event: &HubEvent) -> bool {
+    event.channel.as_ref().is_none_or(|channel| channels.contains(channel))
+}
+
+fn events(
+    subscription: super::hub::EventSubscription,
+    channels: HashSet<String>,
+    connection: SseConnection,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    let mut replay: Vec<Event> = subscription
+        .replay
+        .iter()
+        .filter(|event| wanted(&channels, event))
+        .map(to_event)
+        .collect();
+    if subscription.missed {
+        replay.insert(0, lagged_event());
+    }
+
+    let live = stream::unfold(
+        (subscription.rx, channels, connection),
+        |(mut rx, channels, connection)| async move {
+            loop {
+                let event = match rx.recv().await {
+                    Ok(event) if wanted(&channels, &event) => to_event(&event),
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(dropped)) => {
+                        Metrics::global().ws_lagged_messages.inc_by(dropped);
+                        lagged_event()
+                    }
+                    Err(RecvError::Closed) => return None,
+                };
+                return Some((event, (rx, channels, connection)));
+            }
+        },
+    );
+    stream::iter(replay).chain(live).map(Ok)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_channels() {
+        let channels = parse_channels(" orders,,shop ").unwrap();
+        assert_eq!(channels, HashSet::from(["orders".to_string(), "shop".to_string()]));
+        assert!(parse_channels("").unwrap().is_empty());
+        assert!(parse_channels(&"x".repeat(129)).is_err());
+
+        let event = |channel: Option<&str>| HubEvent {
+            id: 1,
+            channel: channel.map(str::to_string),
+            data: String::new(),
+        };
+        assert!(wanted(&channels, &event(Some("shop"))));
+        assert!(wanted(&channels, &event(None)));
+        assert!(!wanted(&channels, &event(Some("room"))));
+    }
+}